@@ -0,0 +1,264 @@
+use crate::question::Question;
+
+/// link target that ends the quiz instead of leading to a question.
+pub const END: &str = "end";
+
+/// true when any question leads somewhere else than the next question in the file.
+pub fn is_branching(questions: &[Question]) -> bool {
+    questions
+        .iter()
+        .any(|question| question.next_on_correct.is_some() || question.next_on_wrong.is_some())
+}
+
+/// position of the question asked after the one at position, none when the quiz is over.
+pub fn next_position(questions: &[Question], position: usize, correct: bool) -> Option<usize> {
+    let question = &questions[position];
+    let link = if correct {
+        &question.next_on_correct
+    } else {
+        &question.next_on_wrong
+    };
+    match link.as_deref() {
+        Some(END) => None,
+        Some(id) => find(questions, id),
+        None => Some(position + 1).filter(|next| *next < questions.len()),
+    }
+}
+
+/// position of the question with the id.
+fn find(questions: &[Question], id: &str) -> Option<usize> {
+    questions
+        .iter()
+        .position(|question| question.id.as_deref() == Some(id))
+}
+
+/// positions the question at position can lead to, after a correct or a wrong answer.
+fn successors(questions: &[Question], position: usize) -> Vec<usize> {
+    let mut successors: Vec<usize> = Vec::new();
+    for correct in [true, false] {
+        if let Some(next) = next_position(questions, position, correct) {
+            if !successors.contains(&next) {
+                successors.push(next);
+            }
+        }
+    }
+    successors
+}
+
+/// Lists everything wrong with the ids and links of the questions, including questions that can
+/// never be reached from the first question and links that lead in a circle.
+pub fn problems(questions: &[Question]) -> Vec<String> {
+    let mut problems: Vec<String> = Vec::new();
+    for (index, question) in questions.iter().enumerate() {
+        if let Some(id) = &question.id {
+            if id.trim().is_empty() {
+                problems.push(format!("question {}: id is empty", index + 1));
+            } else if id == END {
+                problems.push(format!(
+                    "question {}: id '{END}' is kept for ending the quiz",
+                    index + 1
+                ));
+            } else if questions[..index]
+                .iter()
+                .any(|earlier| earlier.id.as_ref() == Some(id))
+            {
+                problems.push(format!(
+                    "question {}: id '{id}' is used more than once",
+                    index + 1
+                ));
+            }
+        }
+        for (name, link) in [
+            ("next_on_correct", &question.next_on_correct),
+            ("next_on_wrong", &question.next_on_wrong),
+        ] {
+            if let Some(link) = link {
+                if link != END && find(questions, link).is_none() {
+                    problems.push(format!(
+                        "question {}: {name} leads to '{link}' but no question has that id",
+                        index + 1
+                    ));
+                }
+            }
+        }
+    }
+    // broken links would make the walk below report misleading gaps
+    if !problems.is_empty() || !is_branching(questions) {
+        return problems;
+    }
+
+    let mut reached = vec![false; questions.len()];
+    let mut stack: Vec<usize> = vec![0];
+    while let Some(position) = stack.pop() {
+        if !reached[position] {
+            reached[position] = true;
+            stack.extend(successors(questions, position));
+        }
+    }
+    for (index, reached) in reached.iter().enumerate() {
+        if !reached {
+            problems.push(format!(
+                "question {} can never be reached from the first question",
+                index + 1
+            ));
+        }
+    }
+
+    // a quiz with a cycle could ask the same question forever
+    let mut state = vec![Visit::New; questions.len()];
+    for start in 0..questions.len() {
+        let mut path: Vec<usize> = Vec::new();
+        find_cycle(questions, start, &mut state, &mut path, &mut problems);
+    }
+    problems
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    OnPath,
+    Done,
+}
+
+/// depth first walk from position, reports every link back to a question on the current path.
+fn find_cycle(
+    questions: &[Question],
+    position: usize,
+    state: &mut [Visit],
+    path: &mut Vec<usize>,
+    problems: &mut Vec<String>,
+) {
+    if state[position] != Visit::New {
+        return;
+    }
+    state[position] = Visit::OnPath;
+    path.push(position);
+    for next in successors(questions, position) {
+        if state[next] == Visit::OnPath {
+            let start = path
+                .iter()
+                .position(|on_path| *on_path == next)
+                .expect("questions on the path are in it");
+            let cycle: Vec<String> = path[start..]
+                .iter()
+                .chain([&next])
+                .map(|position| label(questions, *position))
+                .collect();
+            problems.push(format!(
+                "questions lead in a circle: {}",
+                cycle.join(" -> ")
+            ));
+        } else {
+            find_cycle(questions, next, state, path, problems);
+        }
+    }
+    path.pop();
+    state[position] = Visit::Done;
+}
+
+/// the id of the question, or its number when it has none.
+fn label(questions: &[Question], position: usize) -> String {
+    match &questions[position].id {
+        Some(id) => format!("'{id}'"),
+        None => format!("question {}", position + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    /// one question per line like "a > b / end", its id followed by next_on_correct / next_on_wrong.
+    /// an empty id leaves the question without one
+    fn questions(links: &[&str]) -> Vec<Question> {
+        #[derive(Deserialize)]
+        struct Bank {
+            questions: Vec<Question>,
+        }
+        let mut toml = String::new();
+        for line in links {
+            let (id, next) = line.split_once('>').unwrap_or((line, ""));
+            toml.push_str(&format!(
+                "[[questions]]\nquestion = \"{}\"\nanswers = [\"x\", \"y\"]\ncorrect_answer = 1\n",
+                id.trim()
+            ));
+            if !id.trim().is_empty() {
+                toml.push_str(&format!("id = \"{}\"\n", id.trim()));
+            }
+            let mut next = next.split('/').map(str::trim);
+            if let Some(correct) = next.next().filter(|link| !link.is_empty()) {
+                toml.push_str(&format!("next_on_correct = \"{correct}\"\n"));
+            }
+            if let Some(wrong) = next.next().filter(|link| !link.is_empty()) {
+                toml.push_str(&format!("next_on_wrong = \"{wrong}\"\n"));
+            }
+        }
+        toml::from_str::<Bank>(&toml).unwrap().questions
+    }
+
+    #[test]
+    fn follows_links_and_falls_through_to_the_next_question() {
+        let questions = questions(&["a > c / b", "b > c / c", "c > end", "d"]);
+        assert_eq!(next_position(&questions, 0, true), Some(2));
+        assert_eq!(next_position(&questions, 0, false), Some(1));
+        assert_eq!(next_position(&questions, 2, true), None);
+        // no next_on_wrong, the next question in the file follows
+        assert_eq!(next_position(&questions, 2, false), Some(3));
+        assert_eq!(next_position(&questions, 3, true), None);
+    }
+
+    #[test]
+    fn a_linear_quiz_is_not_branching_and_has_no_problems() {
+        let questions = questions(&["a", "b", ""]);
+        assert!(!is_branching(&questions));
+        assert!(problems(&questions).is_empty());
+    }
+
+    #[test]
+    fn a_remedial_detour_is_valid() {
+        let questions = questions(&["a > c / b", "b", "c > end"]);
+        assert!(is_branching(&questions));
+        assert!(problems(&questions).is_empty());
+    }
+
+    #[test]
+    fn reports_questions_that_can_not_be_reached() {
+        let questions = questions(&["a > c / c", "b", "c > end / end", "d"]);
+        assert_eq!(
+            problems(&questions),
+            vec![
+                "question 2 can never be reached from the first question",
+                "question 4 can never be reached from the first question",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_cycles_with_the_questions_in_them() {
+        let circle = questions(&["a > b / end", "b > c / end", "c > a / end"]);
+        assert_eq!(
+            problems(&circle),
+            vec!["questions lead in a circle: 'a' -> 'b' -> 'c' -> 'a'"]
+        );
+        // a question retried until it is right is a cycle too
+        let retry = questions(&["a > end / a"]);
+        assert_eq!(
+            problems(&retry),
+            vec!["questions lead in a circle: 'a' -> 'a'"]
+        );
+    }
+
+    #[test]
+    fn reports_broken_ids_and_links() {
+        let questions = questions(&["a > missing", "a", "end"]);
+        assert_eq!(
+            problems(&questions),
+            vec![
+                "question 1: next_on_correct leads to 'missing' but no question has that id",
+                "question 2: id 'a' is used more than once",
+                "question 3: id 'end' is kept for ending the quiz",
+            ]
+        );
+    }
+}
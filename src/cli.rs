@@ -0,0 +1,589 @@
+use anyhow::{anyhow, bail, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::gamemaker;
+use crate::history::History;
+use crate::riddler;
+
+const USAGE: &str = "\
+Usage: rust_quiz_game [COMMAND]
+
+Starts the interactive menu when no command is given.
+
+Commands:
+  play <quiz>        Start a stored quiz straight away
+  resume <save>      Continue a quiz from a save file
+  list               List every stored quiz
+  validate <file>... Check quiz files for mistakes
+  import <file>      Copy a quiz file into your quiz directory
+  export <quiz>      Write a stored quiz out as a quiz file
+  stats              Show results of finished quizes
+  help [command]     Show help for a command
+
+Global flags:
+  --quiz-dir <dir>   Also load quiz files from dir, can be given more than once.
+                     Quizes in the user quiz dir are always loaded:
+                     $XDG_DATA_HOME/rust_quiz_game/quizzes
+  --seed <number>    Draw, order and shuffle new quizes the same way every time.
+
+Run 'rust_quiz_game <command> --help' for the flags of a command.";
+
+const PLAY_USAGE: &str = "\
+Usage: rust_quiz_game play <quiz>
+
+Starts a single examination of the named quiz, skipping the menus.
+The quiz name is not case sensitive, quote it if it has spaces.";
+
+const RESUME_USAGE: &str = "\
+Usage: rust_quiz_game resume <save>
+
+Continues a single examination from a file written by 'save and quit'.";
+
+const LIST_USAGE: &str = "\
+Usage: rust_quiz_game list [--long]
+
+Lists every stored quiz and where it was loaded from.
+
+Flags:
+  -l, --long    Also show the number of questions in each quiz";
+
+const VALIDATE_USAGE: &str = "\
+Usage: rust_quiz_game validate <file>...
+
+Checks that each quiz file can be loaded and that every question can be asked,
+prints a line per file.
+Exits with an error if any file is invalid.";
+
+const IMPORT_USAGE: &str = "\
+Usage: rust_quiz_game import <file> [--force]
+
+Validates a quiz file and copies it into your quiz directory as <name>.toml.
+
+Flags:
+  -f, --force   Overwrite a quiz file of the same name";
+
+const EXPORT_USAGE: &str = "\
+Usage: rust_quiz_game export <quiz> [--output <path>]
+
+Writes a stored quiz as a quiz file, to stdout unless an output is given.
+
+Flags:
+  -o, --output <path>   File to write the quiz to";
+
+const STATS_USAGE: &str = "\
+Usage: rust_quiz_game stats [--quiz <name>]
+
+Shows attempts, best and average scores of finished quizes.
+
+Flags:
+  -q, --quiz <name>   Only show stats for one quiz";
+
+/// Everything the game can be asked to do from the command line.
+#[derive(Debug)]
+pub enum Command {
+    Menu,
+    Play {
+        quiz: String,
+    },
+    Resume {
+        save: PathBuf,
+    },
+    List {
+        long: bool,
+    },
+    Validate {
+        files: Vec<PathBuf>,
+    },
+    Import {
+        file: PathBuf,
+        force: bool,
+    },
+    Export {
+        quiz: String,
+        output: Option<PathBuf>,
+    },
+    Stats {
+        quiz: Option<String>,
+    },
+    Help(&'static str),
+}
+
+/// Flags that apply to every command, laid over the config file once a command needs it.
+#[derive(Debug, Default)]
+pub struct GlobalFlags {
+    pub extra_quiz_dirs: Vec<PathBuf>,
+    pub seed: Option<u64>,
+}
+
+impl GlobalFlags {
+    /// loads the config file, so a broken file only stops the commands that use it.
+    fn config(&self) -> Result<Config> {
+        let mut config = Config::load().map_err(|e| {
+            anyhow!(
+                "Could not read config file {}: {e}",
+                Config::path().display()
+            )
+        })?;
+        config.extra_quiz_dirs = self.extra_quiz_dirs.clone();
+        config.seed = self.seed;
+        Ok(config)
+    }
+}
+
+/// Turns the program arguments into a command and the global flags.
+/// Errors carry a message meant for the user.
+pub fn parse(args: &[String]) -> Result<(Command, GlobalFlags), String> {
+    let (args, flags) = parse_global_flags(args)?;
+    Ok((parse_command(&args)?, flags))
+}
+
+/// pulls global flags out of the arguments, they may appear before or after the command.
+fn parse_global_flags(args: &[String]) -> Result<(Vec<String>, GlobalFlags), String> {
+    let mut flags = GlobalFlags::default();
+    let mut remaining: Vec<String> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--quiz-dir" {
+            match args.next() {
+                Some(dir) => flags.extra_quiz_dirs.push(PathBuf::from(dir)),
+                None => return Err(format!("--quiz-dir needs a value.\n\n{USAGE}")),
+            }
+        } else if arg == "--seed" {
+            match args.next().map(|seed| seed.parse::<u64>()) {
+                Some(Ok(seed)) if seed <= riddler::MAX_SEED => flags.seed = Some(seed),
+                Some(_) => {
+                    return Err(format!(
+                        "--seed needs a whole number from 0 to {}.\n\n{USAGE}",
+                        riddler::MAX_SEED
+                    ))
+                }
+                None => return Err(format!("--seed needs a value.\n\n{USAGE}")),
+            }
+        } else {
+            remaining.push(arg.clone());
+        }
+    }
+    Ok((remaining, flags))
+}
+
+fn parse_command(args: &[String]) -> Result<Command, String> {
+    let Some(command) = args.get(1) else {
+        return Ok(Command::Menu);
+    };
+    let rest = &args[2..];
+
+    match command.as_str() {
+        "-h" | "--help" => Ok(Command::Help(USAGE)),
+        "help" => match rest.first() {
+            None => Ok(Command::Help(USAGE)),
+            Some(topic) => usage_of(topic)
+                .map(Command::Help)
+                .ok_or(format!("Unknown command '{topic}'.\n\n{USAGE}")),
+        },
+        "play" => {
+            let flags = Flags::parse(rest, PLAY_USAGE, &[], &[])?;
+            if flags.help {
+                return Ok(Command::Help(PLAY_USAGE));
+            }
+            Ok(Command::Play {
+                quiz: flags.single_positional("quiz", PLAY_USAGE)?,
+            })
+        }
+        "resume" => {
+            let flags = Flags::parse(rest, RESUME_USAGE, &[], &[])?;
+            if flags.help {
+                return Ok(Command::Help(RESUME_USAGE));
+            }
+            Ok(Command::Resume {
+                save: flags.single_positional("save", RESUME_USAGE)?.into(),
+            })
+        }
+        "list" => {
+            let flags = Flags::parse(rest, LIST_USAGE, &["-l", "--long"], &[])?;
+            if flags.help {
+                return Ok(Command::Help(LIST_USAGE));
+            }
+            flags.no_positionals(LIST_USAGE)?;
+            Ok(Command::List {
+                long: flags.has("--long"),
+            })
+        }
+        "validate" => {
+            let flags = Flags::parse(rest, VALIDATE_USAGE, &[], &[])?;
+            if flags.help {
+                return Ok(Command::Help(VALIDATE_USAGE));
+            }
+            if flags.positionals.is_empty() {
+                return Err(format!("Missing <file>.\n\n{VALIDATE_USAGE}"));
+            }
+            Ok(Command::Validate {
+                files: flags.positionals.iter().map(PathBuf::from).collect(),
+            })
+        }
+        "import" => {
+            let flags = Flags::parse(rest, IMPORT_USAGE, &["-f", "--force"], &[])?;
+            if flags.help {
+                return Ok(Command::Help(IMPORT_USAGE));
+            }
+            Ok(Command::Import {
+                file: flags.single_positional("file", IMPORT_USAGE)?.into(),
+                force: flags.has("--force"),
+            })
+        }
+        "export" => {
+            let flags = Flags::parse(rest, EXPORT_USAGE, &[], &["-o", "--output"])?;
+            if flags.help {
+                return Ok(Command::Help(EXPORT_USAGE));
+            }
+            Ok(Command::Export {
+                quiz: flags.single_positional("quiz", EXPORT_USAGE)?,
+                output: flags.value("--output").map(PathBuf::from),
+            })
+        }
+        "stats" => {
+            let flags = Flags::parse(rest, STATS_USAGE, &[], &["-q", "--quiz"])?;
+            if flags.help {
+                return Ok(Command::Help(STATS_USAGE));
+            }
+            flags.no_positionals(STATS_USAGE)?;
+            Ok(Command::Stats {
+                quiz: flags.value("--quiz"),
+            })
+        }
+        // older versions took a save file as the only argument
+        save if args.len() == 2 && Path::new(save).is_file() => Ok(Command::Resume {
+            save: PathBuf::from(save),
+        }),
+        unknown => Err(format!("Unknown command '{unknown}'.\n\n{USAGE}")),
+    }
+}
+
+fn usage_of(command: &str) -> Option<&'static str> {
+    match command {
+        "play" => Some(PLAY_USAGE),
+        "resume" => Some(RESUME_USAGE),
+        "list" => Some(LIST_USAGE),
+        "validate" => Some(VALIDATE_USAGE),
+        "import" => Some(IMPORT_USAGE),
+        "export" => Some(EXPORT_USAGE),
+        "stats" => Some(STATS_USAGE),
+        _ => None,
+    }
+}
+
+/// Arguments of a single command split into switches, flags with values and positionals.
+/// Switches and value flags are stored under their long name.
+struct Flags {
+    help: bool,
+    switches: Vec<&'static str>,
+    values: Vec<(&'static str, String)>,
+    positionals: Vec<String>,
+}
+
+impl Flags {
+    /// `switches` and `valued` are lists of short and long spellings, short first.
+    fn parse(
+        args: &[String],
+        usage: &str,
+        switches: &[&'static str],
+        valued: &[&'static str],
+    ) -> Result<Flags, String> {
+        let mut flags = Flags {
+            help: false,
+            switches: Vec::new(),
+            values: Vec::new(),
+            positionals: Vec::new(),
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                flags.help = true;
+            } else if let Some(long) = long_name(switches, arg) {
+                flags.switches.push(long);
+            } else if let Some(long) = long_name(valued, arg) {
+                match args.next() {
+                    Some(value) => flags.values.push((long, value.clone())),
+                    None => return Err(format!("{arg} needs a value.\n\n{usage}")),
+                }
+            } else if arg.starts_with('-') && arg.len() > 1 {
+                return Err(format!("Unknown flag '{arg}'.\n\n{usage}"));
+            } else {
+                flags.positionals.push(arg.clone());
+            }
+        }
+        Ok(flags)
+    }
+
+    fn has(&self, long: &str) -> bool {
+        self.switches.contains(&long)
+    }
+
+    /// last value wins if a flag is given more than once.
+    fn value(&self, long: &str) -> Option<String> {
+        self.values
+            .iter()
+            .rev()
+            .find(|(name, _)| *name == long)
+            .map(|(_, value)| value.clone())
+    }
+
+    fn single_positional(&self, name: &str, usage: &str) -> Result<String, String> {
+        match self.positionals.as_slice() {
+            [value] => Ok(value.clone()),
+            [] => Err(format!("Missing <{name}>.\n\n{usage}")),
+            _ => Err(format!(
+                "To many arguments, expected only <{name}>.\n\n{usage}"
+            )),
+        }
+    }
+
+    fn no_positionals(&self, usage: &str) -> Result<(), String> {
+        match self.positionals.first() {
+            None => Ok(()),
+            Some(arg) => Err(format!("Unexpected argument '{arg}'.\n\n{usage}")),
+        }
+    }
+}
+
+/// finds the long spelling of a flag from pairs of short and long spellings.
+fn long_name(spellings: &[&'static str], arg: &str) -> Option<&'static str> {
+    spellings
+        .chunks(2)
+        .find(|pair| pair.contains(&arg))
+        .map(|pair| pair[pair.len() - 1])
+}
+
+/// Runs a parsed command.
+pub fn run(command: Command, flags: &GlobalFlags) -> Result<()> {
+    match command {
+        Command::Menu => gamemaker::main_loop(None, &flags.config()?),
+        Command::Help(usage) => println!("{usage}"),
+        Command::Play { quiz } => {
+            let config = &flags.config()?;
+            let quizes = riddler::QuizList::load_stored_quizes(config)?;
+            match quizes.ready_quiz(quiz.trim().to_lowercase(), config.seed) {
+                Some(quiz) => gamemaker::main_loop(Some(quiz), config),
+                None => bail!("No quiz named '{quiz}', see 'rust_quiz_game list'."),
+            }
+        }
+        Command::Resume { save } => {
+            let loaded_quiz = riddler::Quiz::load(&save).map_err(|e| {
+                anyhow!(
+                    "Encountered errors while loading saved file: \n{e}\nSomething may be wrong with the format of the file, rendering it useless."
+                )
+            })?;
+            gamemaker::main_loop(Some(loaded_quiz), &flags.config()?);
+        }
+        Command::List { long } => {
+            let quizes = riddler::QuizList::load_stored_quizes(&flags.config()?)?;
+            for quiz in &quizes.quizes {
+                if long {
+                    println!(
+                        "{} ({} questions) - {}",
+                        quiz.quiz_name,
+                        quiz.questions.len(),
+                        quiz.source
+                    );
+                } else {
+                    println!("{} - {}", quiz.quiz_name, quiz.source);
+                }
+            }
+            for problem in &quizes.problems {
+                println!("warning: {problem}");
+            }
+        }
+        Command::Validate { files } => {
+            let mut invalid = 0;
+            for file in &files {
+                match riddler::ReadyQuiz::from_file(file) {
+                    Ok(quiz) => println!(
+                        "{}: ok, '{}' with {} questions",
+                        file.display(),
+                        quiz.quiz_name,
+                        quiz.questions.len()
+                    ),
+                    Err(e) => {
+                        invalid += 1;
+                        println!("{}: {e}", file.display());
+                    }
+                }
+            }
+            if invalid > 0 {
+                bail!("{invalid} of {} quiz files are invalid.", files.len());
+            }
+        }
+        Command::Import { file, force } => {
+            let quiz = riddler::ReadyQuiz::from_file(&file)?;
+            let file_stem = file
+                .file_stem()
+                .ok_or(anyhow!("{} is not a file", file.display()))?;
+            let quiz_dir = riddler::user_quiz_dir();
+            // only .toml files are read from the quiz dir
+            let destination = quiz_dir.join(format!("{}.toml", file_stem.to_string_lossy()));
+            if destination.exists() && !force {
+                bail!(
+                    "{} already exists, use --force to overwrite it.",
+                    destination.display()
+                );
+            }
+            fs::create_dir_all(&quiz_dir)?;
+            fs::copy(&file, &destination)?;
+            println!(
+                "Imported '{}' to {}.",
+                quiz.quiz_name,
+                destination.display()
+            );
+        }
+        Command::Export { quiz, output } => {
+            let quizes = riddler::QuizList::load_stored_quizes(&flags.config()?)?;
+            let quiz = quizes.find(&quiz.trim().to_lowercase()).ok_or(anyhow!(
+                "No quiz named '{quiz}', see 'rust_quiz_game list'."
+            ))?;
+            let quiz_file = toml::to_string(quiz)?;
+            match output {
+                Some(path) => {
+                    fs::write(&path, quiz_file)?;
+                    println!("Exported '{}' to {}.", quiz.quiz_name, path.display());
+                }
+                None => print!("{quiz_file}"),
+            }
+        }
+        Command::Stats { quiz } => History::load()?.print_stats(quiz.as_deref()),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// parses a command line typed after the program name.
+    fn parse_line(line: &str) -> Result<Command, String> {
+        let mut args = vec!["rust_quiz_game".to_string()];
+        args.extend(line.split_whitespace().map(String::from));
+        parse(&args).map(|(command, _)| command)
+    }
+
+    #[test]
+    fn no_command_starts_the_menu() {
+        assert!(matches!(parse_line(""), Ok(Command::Menu)));
+    }
+
+    #[test]
+    fn commands_take_their_positionals_and_flags() {
+        assert!(matches!(parse_line("play rust"), Ok(Command::Play { quiz }) if quiz == "rust"));
+        assert!(matches!(
+            parse_line("list --long"),
+            Ok(Command::List { long: true })
+        ));
+        assert!(matches!(
+            parse_line("list"),
+            Ok(Command::List { long: false })
+        ));
+        assert!(matches!(
+            parse_line("import quiz.toml -f"),
+            Ok(Command::Import { file, force: true }) if file == Path::new("quiz.toml")
+        ));
+        assert!(matches!(
+            parse_line("export rust -o out.toml"),
+            Ok(Command::Export { quiz, output: Some(output) })
+                if quiz == "rust" && output == Path::new("out.toml")
+        ));
+        assert!(matches!(
+            parse_line("stats --quiz rust --quiz go"),
+            Ok(Command::Stats { quiz: Some(quiz) }) if quiz == "go"
+        ));
+        assert!(matches!(
+            parse_line("validate a.toml b.toml"),
+            Ok(Command::Validate { files }) if files.len() == 2
+        ));
+    }
+
+    #[test]
+    fn quiz_dirs_are_taken_before_or_after_the_command() {
+        let args: Vec<String> = [
+            "rust_quiz_game",
+            "--quiz-dir",
+            "a",
+            "list",
+            "--quiz-dir",
+            "b",
+        ]
+        .map(String::from)
+        .to_vec();
+        let (command, flags) = parse(&args).unwrap();
+        assert!(matches!(command, Command::List { long: false }));
+        assert_eq!(
+            flags.extra_quiz_dirs,
+            vec![PathBuf::from("a"), PathBuf::from("b")]
+        );
+        assert_eq!(
+            parse_line("list --quiz-dir").unwrap_err(),
+            format!("--quiz-dir needs a value.\n\n{USAGE}")
+        );
+    }
+
+    #[test]
+    fn seeds_must_be_whole_numbers_in_range() {
+        let args: Vec<String> = ["rust_quiz_game", "play", "rust", "--seed", "42"]
+            .map(String::from)
+            .to_vec();
+        let (command, flags) = parse(&args).unwrap();
+        assert!(matches!(command, Command::Play { quiz } if quiz == "rust"));
+        assert_eq!(flags.seed, Some(42));
+        let out_of_range = format!(
+            "--seed needs a whole number from 0 to {}.\n\n{USAGE}",
+            riddler::MAX_SEED
+        );
+        assert_eq!(parse_line("--seed -1").unwrap_err(), out_of_range);
+        assert_eq!(
+            parse_line(&format!("--seed {}", riddler::MAX_SEED + 1)).unwrap_err(),
+            out_of_range
+        );
+        assert_eq!(
+            parse_line("list --seed").unwrap_err(),
+            format!("--seed needs a value.\n\n{USAGE}")
+        );
+    }
+
+    #[test]
+    fn help_is_given_per_command() {
+        assert!(matches!(parse_line("--help"), Ok(Command::Help(USAGE))));
+        assert!(matches!(
+            parse_line("help play"),
+            Ok(Command::Help(PLAY_USAGE))
+        ));
+        assert!(matches!(
+            parse_line("stats -h"),
+            Ok(Command::Help(STATS_USAGE))
+        ));
+        // help wins over missing arguments
+        assert!(matches!(
+            parse_line("play --help"),
+            Ok(Command::Help(PLAY_USAGE))
+        ));
+    }
+
+    #[test]
+    fn mistakes_are_reported_with_the_usage() {
+        for (line, message, usage) in [
+            ("play", "Missing <quiz>.", PLAY_USAGE),
+            (
+                "play a b",
+                "To many arguments, expected only <quiz>.",
+                PLAY_USAGE,
+            ),
+            ("list --all", "Unknown flag '--all'.", LIST_USAGE),
+            ("list rust", "Unexpected argument 'rust'.", LIST_USAGE),
+            ("export rust -o", "-o needs a value.", EXPORT_USAGE),
+            ("validate", "Missing <file>.", VALIDATE_USAGE),
+            ("help nothing", "Unknown command 'nothing'.", USAGE),
+            ("nothing", "Unknown command 'nothing'.", USAGE),
+        ] {
+            let error = parse_line(line).unwrap_err();
+            assert_eq!(error, format!("{message}\n\n{usage}"), "{line}");
+        }
+    }
+}
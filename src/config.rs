@@ -0,0 +1,65 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::grading::GradingScale;
+use crate::message_packs::{self, MessagePack};
+use crate::riddler;
+use crate::tools;
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// Settings for the whole game, read from the config file in the user data dir
+/// and the global command line flags.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Config {
+    /// dirs given with --quiz-dir, searched after the user quiz dir
+    #[serde(skip)]
+    pub extra_quiz_dirs: Vec<PathBuf>,
+    /// grading for quizes that do not set their own
+    #[serde(default)]
+    pub grading: GradingScale,
+    /// given with --seed, makes new attempts reproducible
+    #[serde(skip)]
+    pub seed: Option<u64>,
+    /// name of the message pack shown with results, a built-in pack or a file in the user pack dir
+    #[serde(default = "default_message_pack")]
+    pub message_pack: String,
+    /// the loaded message pack, with the default pack filling in letters it leaves out
+    #[serde(skip)]
+    pub messages: MessagePack,
+}
+
+fn default_message_pack() -> String {
+    message_packs::DEFAULT_PACK.to_string()
+}
+
+impl Config {
+    pub fn path() -> PathBuf {
+        tools::data_dir().join(CONFIG_FILE)
+    }
+
+    /// Loads the config file and its message pack, a missing file gives the default config.
+    pub fn load() -> Result<Config> {
+        let path = Config::path();
+        let mut config: Config = if path.exists() {
+            toml::from_str(&fs::read_to_string(path)?)?
+        } else {
+            toml::from_str("")?
+        };
+        config.grading.validate()?;
+        config.messages = MessagePack::load(&config.message_pack)?.or(&MessagePack::clean());
+        Ok(config)
+    }
+
+    /// Every dir to search for quiz files, in load order.
+    /// The bool marks dirs the user asked for, which are reported when missing.
+    pub fn quiz_dirs(&self) -> Vec<(PathBuf, bool)> {
+        let mut quiz_dirs = vec![(riddler::user_quiz_dir(), false)];
+        for dir in &self.extra_quiz_dirs {
+            quiz_dirs.push((dir.clone(), true));
+        }
+        quiz_dirs
+    }
+}
@@ -0,0 +1,396 @@
+use anyhow::{anyhow, bail, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A parsed arithmetic expression over named variables, like `a * (b - 1)`.
+/// Supports + - * / % ^, parentheses, unary minus and the functions abs, min, max,
+/// round, floor, ceil and sqrt.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Number(f64),
+    Variable(String),
+    Negate(Box<Expression>),
+    Binary(Box<Expression>, Operator, Box<Expression>),
+    Call(Function, Vec<Expression>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Power,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Function {
+    Abs,
+    Min,
+    Max,
+    Round,
+    Floor,
+    Ceil,
+    Sqrt,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        match name {
+            "abs" => Some(Function::Abs),
+            "min" => Some(Function::Min),
+            "max" => Some(Function::Max),
+            "round" => Some(Function::Round),
+            "floor" => Some(Function::Floor),
+            "ceil" => Some(Function::Ceil),
+            "sqrt" => Some(Function::Sqrt),
+            _ => None,
+        }
+    }
+
+    /// number of arguments the function takes.
+    fn arity(self) -> usize {
+        match self {
+            Function::Min | Function::Max => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Symbol(char),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "{value}"),
+            Token::Name(name) => write!(f, "{name}"),
+            Token::Symbol(symbol) => write!(f, "{symbol}"),
+        }
+    }
+}
+
+/// splits the text into numbers, names and single character symbols.
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_ascii_digit() || c == '.' || c == '_') {
+                    break;
+                }
+                if c != '_' {
+                    number.push(c);
+                }
+                chars.next();
+            }
+            let value = number
+                .parse()
+                .map_err(|_| anyhow!("'{number}' is not a number"))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                name.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Name(name));
+        } else if "+-*/%^(),".contains(c) {
+            tokens.push(Token::Symbol(c));
+            chars.next();
+        } else {
+            bail!("'{c}' can not be used in an expression");
+        }
+    }
+    Ok(tokens)
+}
+
+/// recursive descent over the tokens, one method per precedence level.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    /// moves past the symbol when it is next, true if it was.
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<()> {
+        if !self.eat(symbol) {
+            bail!("expected '{symbol}'");
+        }
+        Ok(())
+    }
+
+    /// sums and differences, the lowest precedence
+    fn sum(&mut self) -> Result<Expression> {
+        let mut left = self.product()?;
+        loop {
+            let operator = if self.eat('+') {
+                Operator::Add
+            } else if self.eat('-') {
+                Operator::Subtract
+            } else {
+                return Ok(left);
+            };
+            let right = self.product()?;
+            left = Expression::Binary(Box::new(left), operator, Box::new(right));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expression> {
+        let mut left = self.unary()?;
+        loop {
+            let operator = if self.eat('*') {
+                Operator::Multiply
+            } else if self.eat('/') {
+                Operator::Divide
+            } else if self.eat('%') {
+                Operator::Remainder
+            } else {
+                return Ok(left);
+            };
+            let right = self.unary()?;
+            left = Expression::Binary(Box::new(left), operator, Box::new(right));
+        }
+    }
+
+    /// unary minus binds looser than powers, so -2^2 is -4
+    fn unary(&mut self) -> Result<Expression> {
+        if self.eat('-') {
+            return Ok(Expression::Negate(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    /// powers group to the right, so 2^3^2 is 2^9
+    fn power(&mut self) -> Result<Expression> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            let exponent = self.unary()?;
+            return Ok(Expression::Binary(
+                Box::new(base),
+                Operator::Power,
+                Box::new(exponent),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expression> {
+        match self.peek().cloned() {
+            Some(Token::Number(value)) => {
+                self.position += 1;
+                Ok(Expression::Number(value))
+            }
+            Some(Token::Name(name)) => {
+                self.position += 1;
+                if !self.eat('(') {
+                    return Ok(Expression::Variable(name));
+                }
+                let function = Function::from_name(&name)
+                    .ok_or_else(|| anyhow!("there is no function named '{name}'"))?;
+                let mut arguments = vec![self.sum()?];
+                while self.eat(',') {
+                    arguments.push(self.sum()?);
+                }
+                self.expect(')')?;
+                if arguments.len() != function.arity() {
+                    bail!(
+                        "{name} takes {} arguments, found {}",
+                        function.arity(),
+                        arguments.len()
+                    );
+                }
+                Ok(Expression::Call(function, arguments))
+            }
+            Some(Token::Symbol('(')) => {
+                self.position += 1;
+                let inner = self.sum()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            Some(Token::Symbol(symbol)) => bail!("unexpected '{symbol}'"),
+            None => bail!("the expression ends too early"),
+        }
+    }
+}
+
+impl Expression {
+    /// Parses the text, failing with what is wrong and the text itself.
+    pub fn parse(text: &str) -> Result<Expression> {
+        let parse = || -> Result<Expression> {
+            let mut parser = Parser {
+                tokens: tokenize(text)?,
+                position: 0,
+            };
+            let expression = parser.sum()?;
+            if let Some(token) = parser.peek() {
+                bail!("unexpected '{token}' after the end");
+            }
+            Ok(expression)
+        };
+        parse().map_err(|e| anyhow!("'{text}' is not a valid expression: {e}"))
+    }
+
+    /// names of every variable used, each once.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut variables: Vec<&str> = Vec::new();
+        self.collect_variables(&mut variables);
+        variables
+    }
+
+    fn collect_variables<'a>(&'a self, variables: &mut Vec<&'a str>) {
+        match self {
+            Expression::Number(_) => (),
+            Expression::Variable(name) => {
+                if !variables.contains(&name.as_str()) {
+                    variables.push(name);
+                }
+            }
+            Expression::Negate(inner) => inner.collect_variables(variables),
+            Expression::Binary(left, _, right) => {
+                left.collect_variables(variables);
+                right.collect_variables(variables);
+            }
+            Expression::Call(_, arguments) => {
+                for argument in arguments {
+                    argument.collect_variables(variables);
+                }
+            }
+        }
+    }
+
+    /// Computes the value with the given variable values, division by zero gives a non finite number.
+    pub fn evaluate(&self, values: &BTreeMap<String, f64>) -> Result<f64> {
+        Ok(match self {
+            Expression::Number(value) => *value,
+            Expression::Variable(name) => *values
+                .get(name)
+                .ok_or_else(|| anyhow!("'{name}' has no value"))?,
+            Expression::Negate(inner) => -inner.evaluate(values)?,
+            Expression::Binary(left, operator, right) => {
+                let left = left.evaluate(values)?;
+                let right = right.evaluate(values)?;
+                match operator {
+                    Operator::Add => left + right,
+                    Operator::Subtract => left - right,
+                    Operator::Multiply => left * right,
+                    Operator::Divide => left / right,
+                    Operator::Remainder => left % right,
+                    Operator::Power => left.powf(right),
+                }
+            }
+            Expression::Call(function, arguments) => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| argument.evaluate(values))
+                    .collect::<Result<Vec<f64>>>()?;
+                match function {
+                    Function::Abs => arguments[0].abs(),
+                    Function::Min => arguments[0].min(arguments[1]),
+                    Function::Max => arguments[0].max(arguments[1]),
+                    Function::Round => arguments[0].round(),
+                    Function::Floor => arguments[0].floor(),
+                    Function::Ceil => arguments[0].ceil(),
+                    Function::Sqrt => arguments[0].sqrt(),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(text: &str, values: &[(&str, f64)]) -> f64 {
+        let values: BTreeMap<String, f64> = values
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect();
+        Expression::parse(text).unwrap().evaluate(&values).unwrap()
+    }
+
+    #[test]
+    fn products_bind_tighter_than_sums() {
+        assert_eq!(evaluate("2 + 3 * 4", &[]), 14.0);
+        assert_eq!(evaluate("(2 + 3) * 4", &[]), 20.0);
+        assert_eq!(evaluate("10 - 4 - 3", &[]), 3.0);
+        assert_eq!(evaluate("17 % 5 * 2", &[]), 4.0);
+    }
+
+    #[test]
+    fn powers_group_to_the_right_and_above_unary_minus() {
+        assert_eq!(evaluate("2 ^ 3 ^ 2", &[]), 512.0);
+        assert_eq!(evaluate("-2 ^ 2", &[]), -4.0);
+        assert_eq!(evaluate("(-2) ^ 2", &[]), 4.0);
+        assert_eq!(evaluate("2 ^ -1", &[]), 0.5);
+    }
+
+    #[test]
+    fn variables_and_functions() {
+        assert_eq!(evaluate("a * b", &[("a", 6.0), ("b", 7.0)]), 42.0);
+        assert_eq!(evaluate("2 ^ (32 - p) - 2", &[("p", 26.0)]), 62.0);
+        assert_eq!(evaluate("max(a, 3) + abs(-2)", &[("a", 1.0)]), 5.0);
+        assert_eq!(evaluate("round(7 / 2) + floor(2.9)", &[]), 6.0);
+        assert_eq!(evaluate("1_000 * 2", &[]), 2000.0);
+    }
+
+    #[test]
+    fn division_by_zero_is_not_finite() {
+        assert!(!evaluate("1 / a", &[("a", 0.0)]).is_finite());
+    }
+
+    #[test]
+    fn lists_each_variable_once() {
+        let expression = Expression::parse("a * b + a - max(c, b)").unwrap();
+        assert_eq!(expression.variables(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for (text, problem) in [
+            ("(1 + 2", "expected ')'"),
+            ("1 +", "the expression ends too early"),
+            ("1 2", "unexpected '2' after the end"),
+            ("max(1)", "max takes 2 arguments, found 1"),
+            ("abs(1, 2)", "abs takes 1 arguments, found 2"),
+            ("sin(1)", "there is no function named 'sin'"),
+            ("1 $ 2", "'$' can not be used in an expression"),
+            ("1.2.3", "'1.2.3' is not a number"),
+        ] {
+            let error = Expression::parse(text).unwrap_err().to_string();
+            assert!(error.ends_with(problem), "{text}: {error}");
+        }
+    }
+
+    #[test]
+    fn unknown_variables_fail_on_evaluation() {
+        let expression = Expression::parse("a + 1").unwrap();
+        assert!(expression.evaluate(&BTreeMap::new()).is_err());
+    }
+}
@@ -0,0 +1,350 @@
+use anyhow::Result;
+use chrono::{Days, Local, NaiveDate};
+use rand::thread_rng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::question::{Question, UserAnswer};
+use crate::riddler::QuizList;
+use crate::template;
+use crate::tools;
+
+const SCHEDULE_FILE: &str = "schedule.toml";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+/// never seen questions added to a single review, so a big quiz does not flood the first session
+const NEW_CARDS_PER_SESSION: usize = 20;
+/// user input that leaves a review session, progress so far is kept
+const STOP_REVIEWING: &str = "stop reviewing";
+/// correct answers given this fast count as perfect recall, for questions without a time limit
+const PROMPT_SECONDS: f32 = 10.0;
+
+/// Review schedule of one question, following the SM-2 algorithm.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Card {
+    pub quiz_name: String,
+    pub question: String,
+    /// correct reviews in a row
+    pub repetitions: u32,
+    pub interval_days: u64,
+    /// how fast the interval grows, never below 1.3
+    pub ease: f32,
+    /// day the card should be reviewed again, as year-month-day
+    pub due: String,
+}
+
+impl Card {
+    fn new(quiz_name: &str, question: &Question) -> Card {
+        Card {
+            quiz_name: quiz_name.to_string(),
+            question: question.question.clone(),
+            repetitions: 0,
+            interval_days: 0,
+            ease: 2.5,
+            due: today().format(DATE_FORMAT).to_string(),
+        }
+    }
+
+    fn due_date(&self) -> NaiveDate {
+        // a broken date is treated as due, so the card is not lost
+        NaiveDate::parse_from_str(&self.due, DATE_FORMAT).unwrap_or(NaiveDate::MIN)
+    }
+
+    fn is_due(&self) -> bool {
+        self.due_date() <= today()
+    }
+
+    /// Moves the card to its next review date given a recall quality from 0 (forgot) to 5 (perfect).
+    pub fn review(&mut self, quality: u8) {
+        let quality = quality.min(5);
+        if quality >= 3 {
+            self.interval_days = match self.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (self.interval_days as f32 * self.ease).round() as u64,
+            };
+            self.repetitions += 1;
+        } else {
+            self.repetitions = 0;
+            self.interval_days = 1;
+        }
+        let missed = (5 - quality) as f32;
+        self.ease = (self.ease + 0.1 - missed * (0.08 + missed * 0.02)).max(1.3);
+        self.due = today()
+            .checked_add_days(Days::new(self.interval_days))
+            .unwrap_or(NaiveDate::MAX)
+            .format(DATE_FORMAT)
+            .to_string();
+    }
+}
+
+/// Recall quality of an answer for SM-2, from the share of points it earned.
+/// Correct answers are perfect when given promptly, within half the time limit or PROMPT_SECONDS.
+pub fn recall_quality(user_answer: &UserAnswer) -> u8 {
+    let max_points = user_answer.question.max_points();
+    let share = if max_points > 0.0 {
+        user_answer.points / max_points
+    } else {
+        0.0
+    };
+    let prompt_seconds = match user_answer.question.time_limit {
+        Some(time_limit) => time_limit as f32 / 2.0,
+        None => PROMPT_SECONDS,
+    };
+    if user_answer.is_correct() && user_answer.seconds_taken <= prompt_seconds {
+        5
+    } else if user_answer.is_correct() {
+        4
+    } else if share >= 0.5 {
+        3
+    } else if share > 0.0 {
+        2
+    } else {
+        1
+    }
+}
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Every card the user has reviewed, stored in the user data dir.
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct Schedule {
+    #[serde(default)]
+    pub cards: Vec<Card>,
+}
+
+impl Schedule {
+    fn path() -> PathBuf {
+        tools::data_dir().join(SCHEDULE_FILE)
+    }
+
+    /// Loads the schedule file, a missing file is an empty schedule.
+    pub fn load() -> Result<Schedule> {
+        let path = Schedule::path();
+        if !path.exists() {
+            return Ok(Schedule::default());
+        }
+        let toml_str = fs::read_to_string(path)?;
+        Ok(toml::from_str(&toml_str)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Schedule::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    fn find(&self, quiz_name: &str, question: &Question) -> Option<usize> {
+        self.cards
+            .iter()
+            .position(|card| card.quiz_name == quiz_name && card.question == question.question)
+    }
+
+    /// Collects due questions from every quiz, most overdue first,
+    /// followed by a limited number of questions that were never reviewed.
+    pub fn due_today(&self, quizes: &QuizList) -> Vec<(String, Question)> {
+        let mut due: Vec<(NaiveDate, String, Question)> = Vec::new();
+        let mut new: Vec<(String, Question)> = Vec::new();
+        for quiz in &quizes.quizes {
+            for question in &quiz.questions {
+                match self.find(&quiz.quiz_name, question) {
+                    Some(index) if self.cards[index].is_due() => due.push((
+                        self.cards[index].due_date(),
+                        quiz.quiz_name.clone(),
+                        question.clone(),
+                    )),
+                    Some(_) => (),
+                    None => new.push((quiz.quiz_name.clone(), question.clone())),
+                }
+            }
+        }
+        due.sort_by_key(|(due_date, _, _)| *due_date);
+
+        let mut session: Vec<(String, Question)> = due
+            .into_iter()
+            .map(|(_, quiz_name, question)| (quiz_name, question))
+            .collect();
+        session.extend(new.into_iter().take(NEW_CARDS_PER_SESSION));
+        session
+    }
+
+    /// Records a review of the question, adding a card for it when it is new.
+    pub fn record(&mut self, quiz_name: &str, question: &Question, quality: u8) {
+        let index = match self.find(quiz_name, question) {
+            Some(index) => index,
+            None => {
+                self.cards.push(Card::new(quiz_name, question));
+                self.cards.len() - 1
+            }
+        };
+        self.cards[index].review(quality);
+    }
+
+    /// earliest review date after today, for telling the user when to come back.
+    fn next_due(&self) -> Option<NaiveDate> {
+        self.cards
+            .iter()
+            .map(Card::due_date)
+            .filter(|due_date| *due_date > today())
+            .min()
+    }
+}
+
+/// Runs a "due today" review over every stored quiz, saving the schedule after each answer.
+pub fn review_due(quizes: &QuizList) -> Result<()> {
+    let mut schedule = Schedule::load()?;
+    let session = schedule.due_today(quizes);
+
+    let mut reviewed = 0;
+    for (number, (quiz_name, question)) in session.iter().enumerate() {
+        tools::clear_terminal();
+        println!(
+            "Review {} of {} - {}\n",
+            number + 1,
+            session.len(),
+            quiz_name
+        );
+        // cards are kept per template, every review draws new numbers
+        let asked = match template::instantiate(question, &mut thread_rng()) {
+            Ok(asked) => asked,
+            Err(e) => {
+                println!("Could not generate '{}': {e}", question.question);
+                continue;
+            }
+        };
+        let user_answer = match asked.ask(STOP_REVIEWING) {
+            Some(user_answer) => user_answer,
+            None => break,
+        };
+        user_answer.print_verdict();
+        user_answer.print_report();
+        user_answer.print_explanation();
+
+        schedule.record(quiz_name, question, recall_quality(&user_answer));
+        schedule.save()?;
+        reviewed += 1;
+        println!("\nPress enter to continue.");
+        tools::read_input();
+    }
+
+    tools::clear_terminal();
+    if session.is_empty() {
+        println!("Nothing is due for review today.");
+    } else {
+        println!("Reviewed {reviewed} of {} due questions.", session.len());
+    }
+    if let Some(next_due) = schedule.next_due() {
+        println!("Next review is due on {}.", next_due.format(DATE_FORMAT));
+    }
+    println!("Press enter to return to main menu.");
+    tools::read_input();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card() -> Card {
+        let question: Question = toml::from_str(
+            r#"
+            question = "pick a"
+            answers = ["a", "b"]
+            correct_answer = 1
+            "#,
+        )
+        .unwrap();
+        Card::new("quiz", &question)
+    }
+
+    fn answer(question: &str, points: f32, seconds_taken: f32) -> UserAnswer {
+        UserAnswer {
+            question: toml::from_str(question).unwrap(),
+            answer: String::new(),
+            points,
+            picked: Vec::new(),
+            blank_answers: Vec::new(),
+            seconds_taken,
+            timed_out: false,
+            skipped: false,
+        }
+    }
+
+    fn assert_ease(card: &Card, ease: f32) {
+        assert!((card.ease - ease).abs() < 1e-4, "ease {}", card.ease);
+    }
+
+    #[test]
+    fn intervals_grow_one_six_then_by_ease() {
+        let mut card = card();
+        card.review(4);
+        assert_eq!((card.repetitions, card.interval_days), (1, 1));
+        card.review(4);
+        assert_eq!((card.repetitions, card.interval_days), (2, 6));
+        card.review(4);
+        assert_eq!((card.repetitions, card.interval_days), (3, 15));
+        // quality 4 keeps the ease where it is
+        assert_ease(&card, 2.5);
+        let due = today().checked_add_days(Days::new(15)).unwrap();
+        assert_eq!(card.due_date(), due);
+        assert!(!card.is_due());
+    }
+
+    #[test]
+    fn ease_follows_the_recall_quality() {
+        let mut card = card();
+        card.review(5);
+        assert_ease(&card, 2.6);
+        card.review(3);
+        assert_ease(&card, 2.46);
+    }
+
+    #[test]
+    fn prompt_correct_answers_are_perfect_recall() {
+        let untimed = r#"
+            question = "pick a"
+            answers = ["a", "b"]
+            correct_answer = 1
+            "#;
+        assert_eq!(recall_quality(&answer(untimed, 1.0, 3.0)), 5);
+        assert_eq!(recall_quality(&answer(untimed, 1.0, PROMPT_SECONDS)), 5);
+        assert_eq!(recall_quality(&answer(untimed, 1.0, 25.0)), 4);
+        assert_eq!(recall_quality(&answer(untimed, 0.0, 1.0)), 1);
+
+        let timed = r#"
+            question = "pick a"
+            answers = ["a", "b"]
+            correct_answer = 1
+            time_limit = 60
+            "#;
+        assert_eq!(recall_quality(&answer(timed, 1.0, 25.0)), 5);
+        assert_eq!(recall_quality(&answer(timed, 1.0, 45.0)), 4);
+    }
+
+    #[test]
+    fn forgetting_starts_over_and_ease_never_drops_below_1_3() {
+        let mut card = card();
+        card.review(4);
+        card.review(4);
+        card.review(1);
+        assert_eq!((card.repetitions, card.interval_days), (0, 1));
+        assert_ease(&card, 1.96);
+        for _ in 0..5 {
+            card.review(0);
+        }
+        assert_ease(&card, 1.3);
+    }
+
+    #[test]
+    fn new_and_broken_dates_are_due() {
+        let mut card = card();
+        assert!(card.is_due());
+        card.due = "not a date".to_string();
+        assert!(card.is_due());
+    }
+}
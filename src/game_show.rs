@@ -0,0 +1,177 @@
+use colored::Colorize;
+
+use crate::riddler;
+use crate::tools;
+
+const MIN_PLAYERS: usize = 2;
+const MAX_PLAYERS: usize = 8;
+
+/// user input that ends a game show early and skips straight to the podium.
+const END_GAME: &str = "end game";
+
+/// A single contestant in the game show.
+#[derive(Debug, Clone)]
+pub struct Player {
+    pub name: String,
+    pub score: f32,
+    pub answered: u32,
+}
+
+impl Player {
+    fn new(name: String) -> Player {
+        Player {
+            name,
+            score: 0.0,
+            answered: 0,
+        }
+    }
+}
+
+/// Holds every player of a game show, in turn order.
+pub struct Scoreboard(pub Vec<Player>);
+
+impl Scoreboard {
+    /// prompts the host for the number of players and their names.
+    /// returns none if the host returns to the start up screen.
+    pub fn register_players() -> Option<Scoreboard> {
+        let player_count = loop {
+            println!(
+                "How many players are joining? ({MIN_PLAYERS}-{MAX_PLAYERS}) or return by entering 'start up screen'"
+            );
+            let user_input = tools::read_input();
+            if user_input == "start up screen" {
+                return None;
+            }
+            match user_input.parse::<usize>() {
+                Ok(count) if (MIN_PLAYERS..=MAX_PLAYERS).contains(&count) => break count,
+                _ => println!("Please enter a number between {MIN_PLAYERS} and {MAX_PLAYERS}."),
+            }
+        };
+
+        let mut players: Vec<Player> = Vec::new();
+        while players.len() < player_count {
+            println!("Enter the name of player {}:", players.len() + 1);
+            let name = tools::read_raw_input();
+            if name.is_empty() {
+                println!("A player needs a name.");
+                continue;
+            }
+            if players
+                .iter()
+                .any(|player| player.name.to_lowercase() == name.to_lowercase())
+            {
+                println!("{name} is already playing, please pick another name.");
+                continue;
+            }
+            players.push(Player::new(name));
+        }
+        Some(Scoreboard(players))
+    }
+
+    /// prints every player sorted by score, highest first.
+    pub fn print_standings(&self) {
+        println!("{}", "Scoreboard".bold());
+        for (place, player) in self.ranked().iter().enumerate() {
+            println!(
+                "{}. {} - {} points after {} questions",
+                place + 1,
+                player.name,
+                tools::format_points(player.score),
+                player.answered
+            );
+        }
+        println!();
+    }
+
+    /// prints the final podium, players sharing a score share a step.
+    pub fn print_podium(&self) {
+        println!("{}", "Final Results".bold());
+        let ranked = self.ranked();
+        let mut place = 0;
+        let mut previous_score: Option<f32> = None;
+        for (position, player) in ranked.iter().enumerate() {
+            if previous_score != Some(player.score) {
+                place = position + 1;
+                previous_score = Some(player.score);
+            }
+            let line = format!(
+                "{}. {} with {} points",
+                place,
+                player.name,
+                tools::format_points(player.score)
+            );
+            match place {
+                1 => println!("{}", line.yellow().bold()),
+                2 => println!("{}", line.white().bold()),
+                3 => println!("{}", line.red()),
+                _ => println!("{line}"),
+            }
+        }
+        println!();
+    }
+
+    fn ranked(&self) -> Vec<Player> {
+        let mut ranked = self.0.clone();
+        // stable sort keeps turn order between tied players
+        ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+        ranked
+    }
+}
+
+/// Runs a local hot-seat game show, players take turns answering questions from the given quiz.
+/// Every player gets the same number of questions, left over questions are not asked.
+pub fn play(quiz: riddler::Quiz, mut scoreboard: Scoreboard) {
+    let player_count = scoreboard.0.len();
+    let rounds = quiz.questions.len() / player_count;
+    if rounds == 0 {
+        println!(
+            "{} only has {} questions, not enough for {} players.",
+            quiz.quiz_name,
+            quiz.questions.len(),
+            player_count
+        );
+        println!("Press enter to return to main menu.");
+        tools::read_input();
+        return;
+    }
+
+    let mut questions = quiz.questions.iter().enumerate();
+    'rounds: for round in 1..=rounds {
+        for player in scoreboard.0.iter_mut() {
+            let (position, question) = questions
+                .next()
+                .expect("rounds are limited to the available questions");
+            tools::clear_terminal();
+            println!(
+                "Round {round} of {rounds} - {}'s turn\n",
+                player.name.bold()
+            );
+            let mut user_answer =
+                match question.ask_before(END_GAME, None, quiz.layout_seed(position)) {
+                    Some(user_answer) => user_answer,
+                    None => break 'rounds,
+                };
+            quiz.penalize(&mut user_answer);
+            user_answer.print_verdict();
+            user_answer.print_report();
+            user_answer.print_explanation();
+            player.score += user_answer.points;
+            player.answered += 1;
+            println!("Press enter to continue.");
+            tools::read_input();
+        }
+
+        tools::clear_terminal();
+        println!("End of round {round} of {rounds}\n");
+        scoreboard.print_standings();
+        if round < rounds {
+            println!("Press enter to start the next round.");
+            tools::read_input();
+        }
+    }
+
+    tools::clear_terminal();
+    scoreboard.print_podium();
+    println!("Press enter to return to main menu.");
+    tools::read_input();
+}
@@ -1,11 +1,16 @@
+use crate::branching;
+use crate::config::Config;
+use crate::flashcards;
+use crate::game_show;
 use crate::riddler;
 use crate::tools;
-use std::path::Path;
 
 /// framing enum for the whole game, at all times you will be in one of these states.
 enum GameState {
     StartUpScreen,
     SingleExamination,
+    StudyMode,
+    FlashcardReview,
     GameShow,
     QuitGame,
 }
@@ -17,6 +22,8 @@ fn handle_user_action() -> GameState {
         println!("Please type one of the following game modes or type 'exit' to quit.");
         println!("Start up Screen");
         println!("Single Examination");
+        println!("Study Mode");
+        println!("Flashcard Review");
         println!("Game Show");
 
         let user_action = tools::read_input();
@@ -26,6 +33,8 @@ fn handle_user_action() -> GameState {
             "exit" => return GameState::QuitGame,
             "start up screen" => return GameState::StartUpScreen,
             "single examination" => return GameState::SingleExamination,
+            "study mode" => return GameState::StudyMode,
+            "flashcard review" => return GameState::FlashcardReview,
             "game show" => return GameState::GameShow,
             _ => println!("not a valid action, please enter one of the game modes as displayed."),
         }
@@ -33,24 +42,13 @@ fn handle_user_action() -> GameState {
 }
 
 // main loop for switching between game states
-pub fn main_loop(arg_file: Option<String>) {
+/// a quiz picked or resumed from the command line is played on its own, the game ends with its result.
+pub fn main_loop(quiz: Option<riddler::Quiz>, config: &Config) {
     tools::clear_terminal();
-    let mut game_state: GameState = match arg_file {
-        Some(arg_file) => {
-            let file_path: &Path = Path::new(&arg_file);
-
-            let loaded_quiz: riddler::Quiz = match riddler::Quiz::load(file_path) {
-                Ok(saved_quiz) => saved_quiz,
-                Err(e) => {
-                    println!("Encountered errors while loading saved file: \n{e}");
-                    println!(
-                        "Something may be wrong with the format of the file, rendering it useless."
-                    );
-                    println!("Please start the program again without the file as an arguement.");
-                    return;
-                }
-            };
-            single_examination(Some(loaded_quiz))
+    let mut game_state: GameState = match quiz {
+        Some(quiz) => {
+            examine(quiz, config);
+            GameState::QuitGame
         }
         None => start_up_screen(),
     };
@@ -59,8 +57,10 @@ pub fn main_loop(arg_file: Option<String>) {
 
         game_state = match game_state {
             GameState::StartUpScreen => start_up_screen(),
-            GameState::SingleExamination => single_examination(None),
-            GameState::GameShow => game_show(),
+            GameState::SingleExamination => single_examination(config),
+            GameState::StudyMode => study_mode(config),
+            GameState::FlashcardReview => flashcard_review(config),
+            GameState::GameShow => game_show(config),
             GameState::QuitGame => break,
         };
     }
@@ -71,40 +71,20 @@ pub fn main_loop(arg_file: Option<String>) {
 /// Currently doesnt do anything but welcome user to game.
 fn start_up_screen() -> GameState {
     println!("Welcome To Quiz Show!\n");
-    println!("To load a save file, please input the file name of a saved file as an arguement when starting the quiz game.");
+    println!("To load a save file, start the quiz game with 'resume <save file>'. See '--help' for more.");
     handle_user_action()
 }
 
 /// Game state - Single Examination
 /// Guides user through quiz, prompts for every question and returns result upon completion.
-fn single_examination(saved_quiz: Option<riddler::Quiz>) -> GameState {
-    let quiz: Option<riddler::Quiz> = match saved_quiz {
-        None => {
-            let quizes = match riddler::QuizList::load_stored_quizes() {
-                Ok(quizes) => quizes,
-                Err(e) => {
-                    println!("Error on loading stored quizes: {e}");
-                    return GameState::StartUpScreen; // leads to a reset so you dont end up seeing that error
-                }
-            };
-            println!("Quizes available for testing:");
-            for quiz in quizes.0 {
-                println!("{}", quiz.quiz_name);
-            }
-            prompt_for_quiz() // can return none if user returns to start up screen or error on loading quizes
-        }
-        Some(saved_quiz) => Some(saved_quiz),
-    };
-
-    // catch user prompt to return to start up screen or unwrap
-    let quiz = match quiz {
+fn single_examination(config: &Config) -> GameState {
+    // can return none if user returns to start up screen or error on loading quizes
+    let quiz = match choose_stored_quiz("Quizes available for testing:", config) {
         None => return GameState::StartUpScreen,
         Some(quiz) => quiz,
     };
 
-    if let Some(quiz) = quiz.begin_quiz() {
-        quiz.show_result();
-    } else {
+    if !examine(quiz, config) {
         // saving and quiting returns none, thus quiting the game after logic for saving state
         return GameState::QuitGame;
     }
@@ -112,16 +92,100 @@ fn single_examination(saved_quiz: Option<riddler::Quiz>) -> GameState {
     handle_user_action()
 }
 
-fn game_show() -> GameState {
-    println!("Apologies, this game mode has not been implemented yet.");
+/// asks every question of the quiz and shows the result.
+/// returns false if the user saved and quit before the end.
+fn examine(mut quiz: riddler::Quiz, config: &Config) -> bool {
+    // a resumed quiz keeps the choice made when it was started
+    if quiz.user_answers.is_empty() && quiz.has_explanations() {
+        quiz.prompt_for_explanations();
+    }
+
+    match quiz.begin_quiz() {
+        Some(quiz) => {
+            quiz.show_result(config);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Game state - Study Mode
+/// Like single examination but shows the correct answer after every question,
+/// missed questions come back at the end until they are answered correctly.
+fn study_mode(config: &Config) -> GameState {
+    let quiz = match choose_stored_quiz("Quizes available for studying:", config) {
+        Some(quiz) => quiz,
+        None => return GameState::StartUpScreen,
+    };
+    quiz.study();
     handle_user_action()
 }
 
-fn prompt_for_quiz() -> Option<riddler::Quiz> {
+/// Game state - Flashcard Review
+/// Asks the questions of every stored quiz that are due today, spaced out by how well they were known.
+fn flashcard_review(config: &Config) -> GameState {
+    let quizes = match riddler::QuizList::load_stored_quizes(config) {
+        Ok(quizes) => quizes,
+        Err(e) => {
+            println!("Error on loading stored quizes: {e}");
+            return GameState::StartUpScreen;
+        }
+    };
+    if let Err(e) = flashcards::review_due(&quizes) {
+        println!("Error on reviewing flashcards: {e}");
+    }
+    handle_user_action()
+}
+
+/// Game state - Game Show
+/// Local multiplayer, players take turns answering questions and a scoreboard is kept between rounds.
+fn game_show(config: &Config) -> GameState {
+    println!("Welcome to the Game Show!\n");
+    let quiz = match choose_stored_quiz("Quizes available for the show:", config) {
+        Some(quiz) => quiz,
+        None => return GameState::StartUpScreen,
+    };
+    // players take turns in file order, which would hand remedial questions to the next player
+    if branching::is_branching(&quiz.questions) {
+        println!(
+            "{} picks questions by the previous answer and can not be played as a game show.",
+            quiz.quiz_name
+        );
+        println!("Press enter to return to main menu.");
+        tools::read_input();
+        return GameState::StartUpScreen;
+    }
+
+    // players only sign up once the quiz is known to work as a game show
+    let scoreboard = match game_show::Scoreboard::register_players() {
+        Some(scoreboard) => scoreboard,
+        None => return GameState::StartUpScreen,
+    };
+    game_show::play(quiz, scoreboard);
+    handle_user_action()
+}
+
+/// lists the stored quizes under the heading and lets the user pick one.
+/// returns none if the user returns to the start up screen or the quizes could not be loaded.
+fn choose_stored_quiz(heading: &str, config: &Config) -> Option<riddler::Quiz> {
+    let quizes = match riddler::QuizList::load_stored_quizes(config) {
+        Ok(quizes) => quizes,
+        Err(e) => {
+            println!("Error on loading stored quizes: {e}");
+            return None; // leads to a reset so you dont end up seeing that error
+        }
+    };
+    println!("{heading}");
+    quizes.print_catalog(None);
+    prompt_for_quiz(heading, config)
+}
+
+/// asks for a quiz name until one is found, filter commands list the catalog again narrowed down.
+fn prompt_for_quiz(heading: &str, config: &Config) -> Option<riddler::Quiz> {
     let selected_quiz: Option<riddler::Quiz>;
 
     loop {
-        let quizes = match riddler::QuizList::load_stored_quizes() {
+        let quizes = match riddler::QuizList::load_stored_quizes(config) {
             Ok(quizes) => quizes,
             Err(e) => {
                 println!("Error on loading stored quizes: {e}");
@@ -130,13 +194,24 @@ fn prompt_for_quiz() -> Option<riddler::Quiz> {
             }
         };
         println!("Please enter one of the above displayed quizes to start, or return by entering 'start up screen'");
+        println!("Type 'tag <tag>' or 'difficulty <level>' to only list quizes with such questions, 'all' to list every quiz.");
         let user_input = tools::read_input();
 
         if user_input == "start up screen" {
             return None;
         }
+        // a quiz named like a filter command is still started by name
+        if quizes.find(&user_input).is_none() {
+            let filter = riddler::QuizFilter::parse(&user_input);
+            if filter.is_some() || user_input == "all" {
+                tools::clear_terminal();
+                println!("{heading}");
+                quizes.print_catalog(filter.as_ref());
+                continue;
+            }
+        }
 
-        let user_selected_quiz = quizes.ready_quiz(user_input);
+        let user_selected_quiz = quizes.ready_quiz(user_input, config.seed);
         if user_selected_quiz.is_none() {
             println!("Quiz not available, confirm spelling.");
             continue;
@@ -0,0 +1,465 @@
+use anyhow::{bail, Result};
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fmt;
+
+use crate::message_packs::MessagePack;
+
+/// How a quiz result is graded, set per quiz or for every quiz in the config file.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(tag = "scale", rename_all = "snake_case")]
+pub enum GradingScale {
+    /// US letter grades from A+ to F-
+    #[default]
+    Letter,
+    /// passed at or above pass_at percent, failed below
+    PassFail { pass_at: u8 },
+    /// only the percentage, no grade
+    Percentage,
+    /// German school grades from 1 (sehr gut) to 6 (ungenügend)
+    German,
+    /// named bands, each from its lowest percentage up to the next band
+    Bands { bands: Vec<Band> },
+}
+
+/// A custom grade reached at from percent or more.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Band {
+    pub name: String,
+    pub from: u8,
+    /// shown with the grade, the message pack is used when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<String>,
+}
+
+impl GradingScale {
+    /// German grades with the lowest percentage reaching them and the letter grade of their messages.
+    const GERMAN_GRADES: [(u8, &'static str, LetterGrade); 6] = [
+        (92, "1 (sehr gut)", LetterGrade::A),
+        (81, "2 (gut)", LetterGrade::B),
+        (67, "3 (befriedigend)", LetterGrade::C),
+        (50, "4 (ausreichend)", LetterGrade::D),
+        (30, "5 (mangelhaft)", LetterGrade::F),
+        (0, "6 (ungenügend)", LetterGrade::F),
+    ];
+
+    /// message tones of bands from the highest to the lowest, spread over however many bands there are.
+    const BAND_TONES: [LetterGrade; 5] = [
+        LetterGrade::A,
+        LetterGrade::B,
+        LetterGrade::C,
+        LetterGrade::D,
+        LetterGrade::F,
+    ];
+
+    /// Lists everything wrong with the scale, empty when it can grade every percentage.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = Vec::new();
+        match self {
+            GradingScale::PassFail { pass_at } if *pass_at > 100 => problems.push(format!(
+                "grading pass_at is {pass_at} but must be between 0 and 100"
+            )),
+            GradingScale::Bands { bands } => {
+                if !bands.iter().any(|band| band.from == 0) {
+                    problems.push(
+                        "grading needs a band from 0 so every result gets a grade".to_string(),
+                    );
+                }
+                for (index, band) in bands.iter().enumerate() {
+                    if band.from > 100 {
+                        problems.push(format!(
+                            "grading band '{}' is from {} but must be between 0 and 100",
+                            band.name, band.from
+                        ));
+                    }
+                    if bands[..index]
+                        .iter()
+                        .any(|earlier| earlier.from == band.from)
+                    {
+                        problems.push(format!("grading has more than one band from {}", band.from));
+                    }
+                }
+            }
+            _ => (),
+        }
+        problems
+    }
+
+    /// Fails with every problem of the scale, for scales read from the config file.
+    pub fn validate(&self) -> Result<()> {
+        let problems = self.problems();
+        if !problems.is_empty() {
+            bail!("grading is invalid:\n  {}", problems.join("\n  "));
+        }
+        Ok(())
+    }
+
+    /// the grade for a percentage, none for the percentage only scale.
+    /// messages are picked by the letter grade matching the tone of the grade in its own scale.
+    pub fn grade(&self, percentage: u8, pack: &MessagePack) -> Option<Grade> {
+        let (name, tone) = match self {
+            GradingScale::Letter => {
+                let letter = Letter::from(percentage);
+                (letter.to_string(), letter.grade)
+            }
+            GradingScale::PassFail { pass_at } => {
+                if percentage >= *pass_at {
+                    ("Passed".to_string(), LetterGrade::C)
+                } else {
+                    ("Failed".to_string(), LetterGrade::F)
+                }
+            }
+            GradingScale::Percentage => return None,
+            GradingScale::German => GradingScale::GERMAN_GRADES
+                .iter()
+                .find(|(from, _, _)| percentage >= *from)
+                .map(|(_, name, tone)| (name.to_string(), *tone))?,
+            GradingScale::Bands { bands } => {
+                let mut ranked: Vec<&Band> = bands.iter().collect();
+                ranked.sort_by_key(|band| Reverse(band.from));
+                let rank = ranked.iter().position(|band| percentage >= band.from)?;
+                let band = ranked[rank];
+                if !band.messages.is_empty() {
+                    return Some(Grade {
+                        name: band.name.clone(),
+                        messages: band.messages.clone(),
+                    });
+                }
+                (
+                    band.name.clone(),
+                    GradingScale::band_tone(rank, ranked.len()),
+                )
+            }
+        };
+        Some(Grade {
+            name,
+            messages: pack.messages(&tone).to_vec(),
+        })
+    }
+
+    /// the tone of the band at rank, counted from the highest band. a single band is neutral
+    fn band_tone(rank: usize, band_count: usize) -> LetterGrade {
+        let last_tone = GradingScale::BAND_TONES.len() - 1;
+        let tone = match band_count {
+            0 | 1 => last_tone / 2,
+            _ => (rank * last_tone + (band_count - 1) / 2) / (band_count - 1),
+        };
+        GradingScale::BAND_TONES[tone]
+    }
+}
+
+/// A graded result, with the messages one is picked from.
+pub struct Grade {
+    name: String,
+    messages: Vec<String>,
+}
+
+impl Grade {
+    /// prints a random message from the stored grade messages.
+    pub fn print_random_grade_message(&self) {
+        let mut rng = thread_rng();
+        if let Some(message) = self.messages.choose(&mut rng) {
+            println!("{message}");
+        }
+    }
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Grade: {}", self.name)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum LetterGrade {
+    A,
+    B,
+    C,
+    D,
+    F,
+}
+impl fmt::Display for LetterGrade {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LetterGrade::A => write!(f, "A"),
+            LetterGrade::B => write!(f, "B"),
+            LetterGrade::C => write!(f, "C"),
+            LetterGrade::D => write!(f, "D"),
+            LetterGrade::F => write!(f, "F"),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+enum LetterGradeModifier {
+    Plus,
+    Mid,
+    Minus,
+}
+
+/// US letter grade with a plus, mid or minus modifier.
+struct Letter {
+    grade: LetterGrade,
+    modifer: LetterGradeModifier,
+}
+
+impl fmt::Display for Letter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifer == LetterGradeModifier::Plus {
+            write!(f, "{}+", self.grade)
+        } else if self.modifer == LetterGradeModifier::Mid {
+            write!(f, "{}", self.grade)
+        } else {
+            write!(f, "{}-", self.grade)
+        }
+    }
+}
+
+impl From<u8> for Letter {
+    fn from(score: u8) -> Self {
+        match score {
+            97..=255 => Letter {
+                // Should never be over 100
+                grade: LetterGrade::A,
+                modifer: LetterGradeModifier::Plus,
+            },
+            94..=96 => Letter {
+                grade: LetterGrade::A,
+                modifer: LetterGradeModifier::Mid,
+            },
+            90..=93 => Letter {
+                grade: LetterGrade::A,
+                modifer: LetterGradeModifier::Minus,
+            },
+            87..=89 => Letter {
+                grade: LetterGrade::B,
+                modifer: LetterGradeModifier::Plus,
+            },
+            84..=86 => Letter {
+                grade: LetterGrade::B,
+                modifer: LetterGradeModifier::Mid,
+            },
+            80..=83 => Letter {
+                grade: LetterGrade::B,
+                modifer: LetterGradeModifier::Minus,
+            },
+            77..=79 => Letter {
+                grade: LetterGrade::C,
+                modifer: LetterGradeModifier::Plus,
+            },
+            74..=76 => Letter {
+                grade: LetterGrade::C,
+                modifer: LetterGradeModifier::Mid,
+            },
+            70..=73 => Letter {
+                grade: LetterGrade::C,
+                modifer: LetterGradeModifier::Minus,
+            },
+            67..=69 => Letter {
+                grade: LetterGrade::D,
+                modifer: LetterGradeModifier::Plus,
+            },
+            64..=66 => Letter {
+                grade: LetterGrade::D,
+                modifer: LetterGradeModifier::Mid,
+            },
+            60..=63 => Letter {
+                grade: LetterGrade::D,
+                modifer: LetterGradeModifier::Minus,
+            },
+            55..=59 => Letter {
+                grade: LetterGrade::F,
+                modifer: LetterGradeModifier::Plus,
+            },
+            50..=54 => Letter {
+                grade: LetterGrade::F,
+                modifer: LetterGradeModifier::Mid,
+            },
+            0..=49 => Letter {
+                grade: LetterGrade::F,
+                modifer: LetterGradeModifier::Minus,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scale(toml: &str) -> GradingScale {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn grade_name(scale: &GradingScale, percentage: u8) -> Option<String> {
+        scale
+            .grade(percentage, &MessagePack::clean())
+            .map(|grade| grade.name)
+    }
+
+    #[test]
+    fn letter_grades_change_at_their_lowest_percentage() {
+        let letter = GradingScale::Letter;
+        for (percentage, name) in [
+            (100, "A+"),
+            (97, "A+"),
+            (96, "A"),
+            (90, "A-"),
+            (89, "B+"),
+            (80, "B-"),
+            (79, "C+"),
+            (60, "D-"),
+            (59, "F+"),
+            (55, "F+"),
+            (54, "F"),
+            (50, "F"),
+            (49, "F-"),
+            (0, "F-"),
+        ] {
+            assert_eq!(
+                grade_name(&letter, percentage).as_deref(),
+                Some(name),
+                "{percentage}"
+            );
+        }
+    }
+
+    #[test]
+    fn pass_fail_passes_at_exactly_pass_at() {
+        let pass_fail = scale("scale = \"pass_fail\"\npass_at = 70");
+        assert_eq!(grade_name(&pass_fail, 70).as_deref(), Some("Passed"));
+        assert_eq!(grade_name(&pass_fail, 69).as_deref(), Some("Failed"));
+        assert_eq!(grade_name(&pass_fail, 100).as_deref(), Some("Passed"));
+        // a pass is never cheered on like a top grade, a fail always gets the f messages
+        let pack = MessagePack::clean();
+        assert_eq!(pass_fail.grade(100, &pack).unwrap().messages, pack.c);
+        assert_eq!(pass_fail.grade(69, &pack).unwrap().messages, pack.f);
+        assert_eq!(
+            scale("scale = \"pass_fail\"\npass_at = 101").problems(),
+            vec!["grading pass_at is 101 but must be between 0 and 100"]
+        );
+    }
+
+    #[test]
+    fn german_grades_change_at_their_cutoffs() {
+        let german = GradingScale::German;
+        for (percentage, name) in [
+            (100, "1 (sehr gut)"),
+            (92, "1 (sehr gut)"),
+            (91, "2 (gut)"),
+            (67, "3 (befriedigend)"),
+            (66, "4 (ausreichend)"),
+            (50, "4 (ausreichend)"),
+            (49, "5 (mangelhaft)"),
+            (30, "5 (mangelhaft)"),
+            (29, "6 (ungenügend)"),
+            (0, "6 (ungenügend)"),
+        ] {
+            assert_eq!(
+                grade_name(&german, percentage).as_deref(),
+                Some(name),
+                "{percentage}"
+            );
+        }
+        // messages follow the german grade, not the us letter of the same percentage
+        let pack = MessagePack::clean();
+        for (percentage, messages) in [
+            (92, &pack.a),
+            (67, &pack.c),
+            (66, &pack.d),
+            (50, &pack.d),
+            (49, &pack.f),
+            (0, &pack.f),
+        ] {
+            assert_eq!(
+                &german.grade(percentage, &pack).unwrap().messages,
+                messages,
+                "{percentage}"
+            );
+        }
+    }
+
+    #[test]
+    fn percentage_only_gives_no_grade() {
+        assert!(GradingScale::Percentage
+            .grade(75, &MessagePack::clean())
+            .is_none());
+    }
+
+    #[test]
+    fn bands_can_be_listed_in_any_order() {
+        let bands = scale(
+            r#"
+            scale = "bands"
+            bands = [
+                { name = "Merit", from = 60 },
+                { name = "Fail", from = 0 },
+                { name = "Distinction", from = 85, messages = ["Top marks."] },
+            ]
+            "#,
+        );
+        assert!(bands.problems().is_empty());
+        for (percentage, name) in [
+            (100, "Distinction"),
+            (85, "Distinction"),
+            (84, "Merit"),
+            (60, "Merit"),
+            (59, "Fail"),
+            (0, "Fail"),
+        ] {
+            assert_eq!(
+                grade_name(&bands, percentage).as_deref(),
+                Some(name),
+                "{percentage}"
+            );
+        }
+        let pack = MessagePack::clean();
+        assert_eq!(bands.grade(90, &pack).unwrap().messages, vec!["Top marks."]);
+        // bands without messages take them from the pack by their rank, highest to lowest
+        assert_eq!(bands.grade(60, &pack).unwrap().messages, pack.c);
+        assert_eq!(bands.grade(84, &pack).unwrap().messages, pack.c);
+        assert_eq!(bands.grade(59, &pack).unwrap().messages, pack.f);
+        let two_bands = scale(
+            r#"
+            scale = "bands"
+            bands = [{ name = "Fail", from = 0 }, { name = "Pass", from = 40 }]
+            "#,
+        );
+        assert_eq!(two_bands.grade(40, &pack).unwrap().messages, pack.a);
+        assert_eq!(two_bands.grade(39, &pack).unwrap().messages, pack.f);
+    }
+
+    #[test]
+    fn band_tones_spread_from_a_to_f() {
+        let tones = |band_count| {
+            (0..band_count)
+                .map(|rank| GradingScale::band_tone(rank, band_count))
+                .collect::<Vec<LetterGrade>>()
+        };
+        use LetterGrade::*;
+        assert_eq!(tones(1), vec![C]);
+        assert_eq!(tones(3), vec![A, C, F]);
+        assert_eq!(tones(5), vec![A, B, C, D, F]);
+        assert_eq!(tones(6), vec![A, B, C, C, D, F]);
+    }
+
+    #[test]
+    fn reports_bands_that_leave_results_ungraded() {
+        let bands = scale(
+            r#"
+            scale = "bands"
+            bands = [{ name = "Pass", from = 50 }, { name = "Also pass", from = 50 }, { name = "Over", from = 120 }]
+            "#,
+        );
+        assert_eq!(
+            bands.problems(),
+            vec![
+                "grading needs a band from 0 so every result gets a grade",
+                "grading has more than one band from 50",
+                "grading band 'Over' is from 120 but must be between 0 and 100",
+            ]
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use anyhow::Result;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::tools;
+
+const HISTORY_FILE: &str = "history.toml";
+
+/// Outcome of one finished quiz, kept for the stats command.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Record {
+    pub quiz_name: String,
+    pub score: f32,
+    pub total: f32,
+    pub percentage: u8,
+    pub finished_at: String,
+}
+
+/// Every recorded quiz result, stored in the user data dir.
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct History {
+    #[serde(default)]
+    pub records: Vec<Record>,
+}
+
+impl History {
+    fn path() -> PathBuf {
+        tools::data_dir().join(HISTORY_FILE)
+    }
+
+    /// Loads the history file, a missing file is an empty history.
+    pub fn load() -> Result<History> {
+        let path = History::path();
+        if !path.exists() {
+            return Ok(History::default());
+        }
+        let toml_str = fs::read_to_string(path)?;
+        Ok(toml::from_str(&toml_str)?)
+    }
+
+    /// Appends a finished quiz to the history file.
+    pub fn record(quiz_name: &str, score: f32, total: f32, percentage: u8) -> Result<()> {
+        let mut history = History::load()?;
+        history.records.push(Record {
+            quiz_name: quiz_name.to_string(),
+            score,
+            total,
+            percentage,
+            finished_at: Local::now().format("%d-%m-%Y %H:%M").to_string(),
+        });
+        let path = History::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string(&history)?)?;
+        Ok(())
+    }
+
+    /// Prints attempts, best and average percentage per quiz, optionally for a single quiz.
+    pub fn print_stats(&self, quiz_filter: Option<&str>) {
+        let mut quiz_names: Vec<&str> = Vec::new();
+        for record in &self.records {
+            if !quiz_names.contains(&record.quiz_name.as_str()) {
+                quiz_names.push(&record.quiz_name);
+            }
+        }
+        if let Some(filter) = quiz_filter {
+            quiz_names.retain(|name| name.trim().to_lowercase() == filter.trim().to_lowercase());
+        }
+        if quiz_names.is_empty() {
+            println!("No finished quizes recorded yet.");
+            return;
+        }
+
+        for quiz_name in quiz_names {
+            let records: Vec<&Record> = self
+                .records
+                .iter()
+                .filter(|record| record.quiz_name == quiz_name)
+                .collect();
+            let best = records
+                .iter()
+                .map(|record| record.percentage)
+                .max()
+                .unwrap_or(0);
+            let average = records
+                .iter()
+                .map(|record| record.percentage as u32)
+                .sum::<u32>()
+                / records.len() as u32;
+            let last = records.last().expect("quiz names come from records");
+            println!("{quiz_name}");
+            println!("  attempts: {}", records.len());
+            println!("  best: {best}%  average: {average}%");
+            println!(
+                "  last: {} / {} ({}%) on {}",
+                tools::format_points(last.score),
+                tools::format_points(last.total),
+                last.percentage,
+                last.finished_at
+            );
+        }
+    }
+}
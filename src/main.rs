@@ -1,27 +1,39 @@
+mod branching;
+mod cli;
+mod config;
+mod expression;
+mod flashcards;
+mod game_show;
 mod gamemaker;
+mod grading;
+mod history;
+mod markdown;
+mod message_packs;
+mod pool;
+mod question;
 mod riddler;
+mod template;
+mod text_match;
 mod tools;
 
 use std::env;
-fn main() {
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
     let args: Vec<String> = env::args().collect();
 
-    // TODO: move main_loop to main.rs
-    match read_args(&args) {
-        Ok(None) => gamemaker::main_loop(None),
-        Ok(any_string) => gamemaker::main_loop(any_string),
+    let (command, flags) = match cli::parse(&args) {
+        Ok(parsed) => parsed,
         Err(e) => {
             println!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match cli::run(command, &flags) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            println!("{e}");
+            ExitCode::FAILURE
         }
-    }
-}
-
-fn read_args(args: &[String]) -> Result<Option<String>, String> {
-    if args.len() == 1 {
-        Ok(None)
-    } else if args.len() == 2 {
-        return Ok(Some(args[1].clone()));
-    } else {
-        return Err("To many arguments, not supported.".to_string());
     }
 }
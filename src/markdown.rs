@@ -0,0 +1,516 @@
+use colored::{ColoredString, Colorize};
+use std::env;
+
+/// columns text is wrapped to when the terminal does not say
+const DEFAULT_WIDTH: usize = 80;
+/// code blocks are indented this far and never wrapped
+const CODE_INDENT: &str = "    ";
+const FENCE: &str = "```";
+
+const RUST_KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+/// How a piece of inline text is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct Style {
+    bold: bool,
+    italic: bool,
+    code: bool,
+}
+
+/// A run of text with a single style.
+#[derive(Debug, Clone, PartialEq)]
+struct Span {
+    text: String,
+    style: Style,
+}
+
+/// Renders a markdown subset for the terminal: `inline code`, **bold**, *italic* or _italic_,
+/// and fenced code blocks, with rust highlighted. Lines are wrapped to the terminal width less
+/// indent, every line after the first starts with indent spaces so it lines up behind a label.
+/// Text without any of that markdown is returned exactly as it is.
+pub fn render(text: &str, indent: usize) -> String {
+    if !has_markdown(text) {
+        return text.to_string();
+    }
+    let width = terminal_width().saturating_sub(indent).max(20);
+    let mut lines: Vec<String> = Vec::new();
+    let mut code_language: Option<String> = None;
+    let mut in_block_comment = false;
+    for line in text.lines() {
+        if let Some(language) = line.trim_start().strip_prefix(FENCE) {
+            code_language = match code_language {
+                Some(_) => None,
+                None => Some(language.trim().to_lowercase()),
+            };
+            in_block_comment = false;
+            continue;
+        }
+        match &code_language {
+            Some(language) if language == "rust" || language == "rs" => lines.push(format!(
+                "{CODE_INDENT}{}",
+                highlight_rust(line, &mut in_block_comment)
+            )),
+            Some(_) => lines.push(format!("{CODE_INDENT}{}", line.bright_white())),
+            None => lines.extend(wrap(&parse_inline(line, Style::default()), width)),
+        }
+    }
+    lines.join(&format!("\n{}", " ".repeat(indent)))
+}
+
+/// true when the text has a code fence or any styled inline span.
+fn has_markdown(text: &str) -> bool {
+    text.lines().any(|line| {
+        line.trim_start().starts_with(FENCE)
+            || parse_inline(line, Style::default())
+                .iter()
+                .any(|span| span.style != Style::default())
+    })
+}
+
+/// columns of the terminal, from COLUMNS when the shell exports it.
+fn terminal_width() -> usize {
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+/// splits a line into styled spans.
+fn parse_inline(text: &str, style: Style) -> Vec<Span> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans: Vec<Span> = Vec::new();
+    let mut plain = String::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        // backslash escapes punctuation only, so paths and \n in plain text stay as they are
+        if c == '\\' && chars.get(index + 1).is_some_and(char::is_ascii_punctuation) {
+            plain.push(chars[index + 1]);
+            index += 2;
+            continue;
+        }
+        let styled = if c == '`' {
+            find(&chars, index + 1, |at| chars[at] == '`').map(|end| {
+                let inner: String = chars[index + 1..end].iter().collect();
+                let code = Style {
+                    code: true,
+                    ..style
+                };
+                (
+                    vec![Span {
+                        text: inner,
+                        style: code,
+                    }],
+                    end + 1,
+                )
+            })
+        } else if (c == '*' || c == '_') && chars.get(index + 1) == Some(&c) {
+            emphasis(&chars, index, 2).map(|end| {
+                let inner: String = chars[index + 2..end].iter().collect();
+                let bold = Style {
+                    bold: true,
+                    ..style
+                };
+                (parse_inline(&inner, bold), end + 2)
+            })
+        } else if c == '*' || c == '_' {
+            emphasis(&chars, index, 1).map(|end| {
+                let inner: String = chars[index + 1..end].iter().collect();
+                let italic = Style {
+                    italic: true,
+                    ..style
+                };
+                (parse_inline(&inner, italic), end + 1)
+            })
+        } else {
+            None
+        };
+        match styled {
+            Some((inner, next)) => {
+                if !plain.is_empty() {
+                    spans.push(Span {
+                        text: std::mem::take(&mut plain),
+                        style,
+                    });
+                }
+                spans.extend(inner);
+                index = next;
+            }
+            None => {
+                plain.push(c);
+                index += 1;
+            }
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(Span { text: plain, style });
+    }
+    spans
+}
+
+/// first position from start that passes the test.
+fn find(chars: &[char], start: usize, test: impl Fn(usize) -> bool) -> Option<usize> {
+    (start..chars.len()).find(|at| test(*at))
+}
+
+/// position of the closing delimiter for the run of count delimiters at start.
+/// delimiters must hug the text and sit at word edges, so 2 * 3 * 4, snake_case and ____ stay plain
+fn emphasis(chars: &[char], start: usize, count: usize) -> Option<usize> {
+    let delimiter = chars[start];
+    let is_word = |at: Option<&char>| at.is_some_and(|c| c.is_alphanumeric());
+    let before = start.checked_sub(1).map(|at| &chars[at]);
+    let first = chars.get(start + count)?;
+    if is_word(before) || first.is_whitespace() || *first == delimiter {
+        return None;
+    }
+    find(chars, start + count + 1, |at| {
+        at + count <= chars.len()
+            && chars[at..at + count].iter().all(|c| *c == delimiter)
+            && !chars[at - 1].is_whitespace()
+            && chars[at - 1] != delimiter
+            && chars.get(at + count) != Some(&delimiter)
+            && !is_word(chars.get(at + count))
+    })
+}
+
+/// fills lines up to width with whole words, a word longer than a line gets a line of its own.
+fn wrap(spans: &[Span], width: usize) -> Vec<String> {
+    // a word is made of styled pieces, like `Vec`s
+    let mut words: Vec<Vec<Span>> = Vec::new();
+    let mut word: Vec<Span> = Vec::new();
+    for span in spans {
+        if span.style.code {
+            word.push(span.clone());
+            continue;
+        }
+        let mut piece = String::new();
+        for c in span.text.chars() {
+            if c.is_whitespace() {
+                if !piece.is_empty() {
+                    word.push(Span {
+                        text: std::mem::take(&mut piece),
+                        style: span.style,
+                    });
+                }
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            } else {
+                piece.push(c);
+            }
+        }
+        if !piece.is_empty() {
+            word.push(Span {
+                text: piece,
+                style: span.style,
+            });
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    let mut line_width = 0;
+    for word in words {
+        let word_width: usize = word.iter().map(|span| span.text.chars().count()).sum();
+        if line_width > 0 && line_width + 1 + word_width > width {
+            lines.push(std::mem::take(&mut line));
+            line_width = 0;
+        }
+        if line_width > 0 {
+            line.push(' ');
+            line_width += 1;
+        }
+        for span in &word {
+            line.push_str(&styled(span).to_string());
+        }
+        line_width += word_width;
+    }
+    lines.push(line);
+    lines
+}
+
+fn styled(span: &Span) -> ColoredString {
+    let mut styled = span.text.normal();
+    if span.style.code {
+        styled = styled.yellow();
+    }
+    if span.style.bold {
+        styled = styled.bold();
+    }
+    if span.style.italic {
+        styled = styled.italic();
+    }
+    styled
+}
+
+/// colours a line of rust, in_block_comment carries a /* comment */ over to the next line.
+fn highlight_rust(line: &str, in_block_comment: &mut bool) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut highlighted = String::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let rest: String = chars[index..].iter().collect();
+        if *in_block_comment || rest.starts_with("/*") {
+            // the opening /* must not count as the end
+            let from = if *in_block_comment { 0 } else { 2 };
+            let end = rest[from..]
+                .find("*/")
+                .map(|end| rest[..from + end + 2].chars().count());
+            *in_block_comment = end.is_none();
+            let length = end.unwrap_or(chars.len() - index);
+            let comment: String = chars[index..index + length].iter().collect();
+            highlighted.push_str(&comment.bright_black().to_string());
+            index += length;
+            continue;
+        }
+        if rest.starts_with("//") {
+            highlighted.push_str(&rest.bright_black().to_string());
+            break;
+        }
+        let c = chars[index];
+        let length = if c == '"' {
+            let mut end = index + 1;
+            while end < chars.len() && chars[end] != '"' {
+                end += if chars[end] == '\\' { 2 } else { 1 };
+            }
+            let end = (end + 1).min(chars.len());
+            let text: String = chars[index..end].iter().collect();
+            highlighted.push_str(&text.green().to_string());
+            end - index
+        } else if c == '\'' {
+            // 'a' and '\n' are chars, 'a on its own is a lifetime, '\'' closes after its escape
+            let close = if chars.get(index + 1) == Some(&'\\') {
+                find(&chars, index + 3, |at| chars[at] == '\'')
+            } else {
+                Some(index + 2).filter(|at| chars.get(*at) == Some(&'\''))
+            };
+            match close {
+                Some(close) => {
+                    let text: String = chars[index..=close].iter().collect();
+                    highlighted.push_str(&text.green().to_string());
+                    close + 1 - index
+                }
+                None => {
+                    let end = find(&chars, index + 1, |at| {
+                        !(chars[at].is_alphanumeric() || chars[at] == '_')
+                    })
+                    .unwrap_or(chars.len());
+                    let text: String = chars[index..end].iter().collect();
+                    highlighted.push_str(&text.magenta().to_string());
+                    end - index
+                }
+            }
+        } else if c.is_ascii_digit() {
+            let end = find(&chars, index, |at| {
+                !(chars[at].is_alphanumeric() || chars[at] == '_' || chars[at] == '.')
+            })
+            .unwrap_or(chars.len());
+            let text: String = chars[index..end].iter().collect();
+            highlighted.push_str(&text.yellow().to_string());
+            end - index
+        } else if c.is_alphabetic() || c == '_' {
+            let end = find(&chars, index, |at| {
+                !(chars[at].is_alphanumeric() || chars[at] == '_')
+            })
+            .unwrap_or(chars.len());
+            let word: String = chars[index..end].iter().collect();
+            let word = if RUST_KEYWORDS.contains(&word.as_str()) {
+                word.magenta().bold()
+            } else if chars.get(end) == Some(&'!') {
+                word.blue()
+            } else if word.starts_with(|c: char| c.is_uppercase()) {
+                word.cyan()
+            } else {
+                word.normal()
+            };
+            highlighted.push_str(&word.to_string());
+            end - index
+        } else {
+            highlighted.push(c);
+            1
+        };
+        index += length;
+    }
+    highlighted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the spans of a line as text and a short name of their style.
+    fn spans(text: &str) -> Vec<(String, &'static str)> {
+        parse_inline(text, Style::default())
+            .into_iter()
+            .map(|span| {
+                let style = match (span.style.bold, span.style.italic, span.style.code) {
+                    (_, _, true) => "code",
+                    (true, true, _) => "bold italic",
+                    (true, false, _) => "bold",
+                    (false, true, _) => "italic",
+                    (false, false, _) => "plain",
+                };
+                (span.text, style)
+            })
+            .collect()
+    }
+
+    fn span(text: &str, style: &'static str) -> (String, &'static str) {
+        (text.to_string(), style)
+    }
+
+    /// the text without colours. colours are always on in tests, so no test sees another switch them off
+    fn uncoloured(text: &str) -> String {
+        let mut plain = String::new();
+        let mut in_escape = false;
+        for c in text.chars() {
+            match (in_escape, c) {
+                (false, '\x1b') => in_escape = true,
+                (false, c) => plain.push(c),
+                (true, 'm') => in_escape = false,
+                (true, _) => (),
+            }
+        }
+        plain
+    }
+
+    #[test]
+    fn plain_text_is_returned_exactly() {
+        for text in [
+            "What is 2 * 3 * 4?",
+            "a*b*c and snake_case_names",
+            "  keeps   its spacing\nand its lines  ",
+            "C:\\Users\\me and \\n stay",
+            "____ is a blank, so is **",
+            "*not closed and `not code",
+        ] {
+            assert_eq!(render(text, 4), text);
+        }
+    }
+
+    #[test]
+    fn emphasis_sits_at_word_edges() {
+        assert_eq!(spans("a*b*c"), vec![span("a*b*c", "plain")]);
+        assert_eq!(spans("2 * 3 * 4"), vec![span("2 * 3 * 4", "plain")]);
+        assert_eq!(
+            spans("call self.__init__ or my_var_name"),
+            vec![
+                span("call self.", "plain"),
+                span("init", "bold"),
+                span(" or my_var_name", "plain"),
+            ]
+        );
+        assert_eq!(
+            spans("*a* and _b_"),
+            vec![
+                span("a", "italic"),
+                span(" and ", "plain"),
+                span("b", "italic")
+            ]
+        );
+        assert_eq!(spans("* a *"), vec![span("* a *", "plain")]);
+    }
+
+    #[test]
+    fn unclosed_markers_stay_plain() {
+        assert_eq!(spans("**bold"), vec![span("**bold", "plain")]);
+        assert_eq!(spans("_half"), vec![span("_half", "plain")]);
+        assert_eq!(spans("`code"), vec![span("`code", "plain")]);
+        assert_eq!(
+            spans("**a* b"),
+            vec![span("*", "plain"), span("a", "italic"), span(" b", "plain")]
+        );
+    }
+
+    #[test]
+    fn code_spans_keep_their_markers() {
+        assert_eq!(
+            spans("deref with `*x` or `a * b`"),
+            vec![
+                span("deref with ", "plain"),
+                span("*x", "code"),
+                span(" or ", "plain"),
+                span("a * b", "code"),
+            ]
+        );
+        assert_eq!(spans("`**not bold**`"), vec![span("**not bold**", "code")]);
+    }
+
+    #[test]
+    fn emphasis_nests_and_escapes_stay_literal() {
+        assert_eq!(
+            spans("**bold _both_ `code`**"),
+            vec![
+                span("bold ", "bold"),
+                span("both", "bold italic"),
+                span(" ", "bold"),
+                span("code", "code"),
+            ]
+        );
+        assert_eq!(
+            spans("\\*not italic\\* but *this*"),
+            vec![span("*not italic* but ", "plain"), span("this", "italic")]
+        );
+    }
+
+    #[test]
+    fn wraps_whole_words_and_indents_following_lines() {
+        colored::control::set_override(true);
+        assert_eq!(
+            uncoloured(&render("**Note:** a long sentence that has to wrap", 2)),
+            "Note: a long sentence that has to wrap"
+        );
+        let wrapped = |text: &str, width: usize| -> Vec<String> {
+            wrap(&parse_inline(text, Style::default()), width)
+                .iter()
+                .map(|line| uncoloured(line))
+                .collect()
+        };
+        assert_eq!(
+            wrapped("one two three fourteen", 9),
+            vec!["one two", "three", "fourteen"]
+        );
+        assert_eq!(
+            wrapped("one two three fourteen", 4),
+            vec!["one", "two", "three", "fourteen"]
+        );
+        // a code span with spaces is never split
+        assert_eq!(wrapped("use `a b c` here", 6), vec!["use", "a b c", "here"]);
+    }
+
+    #[test]
+    fn fenced_code_is_indented_and_not_wrapped() {
+        colored::control::set_override(true);
+        let text = "Look at *this*:\n```rust\nlet x = 'a';\n```\nDone.";
+        assert_eq!(
+            uncoloured(&render(text, 3)),
+            "Look at this:\n       let x = 'a';\n   Done."
+        );
+    }
+
+    #[test]
+    fn highlights_char_literals_apart_from_lifetimes() {
+        colored::control::set_override(true);
+        let mut in_block_comment = false;
+        let highlighted = highlight_rust(
+            "fn f<'a>(c: char) { ['\\n', '\\'', 'x'] }",
+            &mut in_block_comment,
+        );
+        assert!(highlighted.contains(&"'a".magenta().to_string()));
+        assert!(highlighted.contains(&"'\\n'".green().to_string()));
+        assert!(highlighted.contains(&"'\\''".green().to_string()));
+        assert!(highlighted.contains(&"'x'".green().to_string()));
+        assert_eq!(
+            uncoloured(&highlighted),
+            "fn f<'a>(c: char) { ['\\n', '\\'', 'x'] }"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::{anyhow, Result};
+use include_dir::{include_dir, Dir};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::grading::LetterGrade;
+use crate::tools;
+
+// rebuild if you change the built-in packs, packs in the user pack dir are read at runtime
+static PACK_DIR: Dir = include_dir!("src/message_packs/");
+
+/// pack used when the config file does not pick one, and for letters a pack leaves out
+pub const DEFAULT_PACK: &str = "clean";
+
+/// Messages shown with a result, a list per letter grade.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+pub struct MessagePack {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub a: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub b: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub c: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub d: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub f: Vec<String>,
+}
+
+/// Directory users can drop their own message packs in, named by file name.
+pub fn user_pack_dir() -> PathBuf {
+    tools::data_dir().join("message_packs")
+}
+
+impl MessagePack {
+    /// Loads a pack by name, a user pack wins over a built-in pack of the same name.
+    pub fn load(name: &str) -> Result<MessagePack> {
+        let file_name = format!("{name}.toml");
+        let user_pack = user_pack_dir().join(&file_name);
+        let toml_str = if user_pack.exists() {
+            fs::read_to_string(user_pack)?
+        } else {
+            PACK_DIR
+                .get_file(&file_name)
+                .and_then(|file| file.contents_utf8())
+                .ok_or_else(|| anyhow!("there is no message pack named '{name}'"))?
+                .to_string()
+        };
+        Ok(toml::from_str(&toml_str)?)
+    }
+
+    /// the built-in default pack, always has messages for every letter.
+    pub fn clean() -> MessagePack {
+        MessagePack::load(DEFAULT_PACK).expect("the clean pack is built in")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == MessagePack::default()
+    }
+
+    /// fills every letter this pack has no messages for from the fallback pack.
+    pub fn or(mut self, fallback: &MessagePack) -> MessagePack {
+        for (messages, fallback_messages) in [
+            (&mut self.a, &fallback.a),
+            (&mut self.b, &fallback.b),
+            (&mut self.c, &fallback.c),
+            (&mut self.d, &fallback.d),
+            (&mut self.f, &fallback.f),
+        ] {
+            if messages.is_empty() {
+                messages.clone_from(fallback_messages);
+            }
+        }
+        self
+    }
+
+    pub fn messages(&self, grade: &LetterGrade) -> &[String] {
+        match grade {
+            LetterGrade::A => &self.a,
+            LetterGrade::B => &self.b,
+            LetterGrade::C => &self.c,
+            LetterGrade::D => &self.d,
+            LetterGrade::F => &self.f,
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::question::Question;
+
+/// Draws a random subset of a large question bank for every attempt.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Pool {
+    /// questions asked per attempt
+    pub draw: usize,
+    /// keeps the share of every tag or difficulty in the drawn questions close to the bank
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stratify_by: Option<Stratify>,
+}
+
+/// What questions are grouped by for a stratified draw.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Stratify {
+    /// the first tag of a question
+    Tag,
+    Difficulty,
+}
+
+impl Pool {
+    /// Lists everything wrong with the pool for a bank of the given size.
+    pub fn problems(&self, bank_size: usize) -> Vec<String> {
+        let mut problems: Vec<String> = Vec::new();
+        if self.draw == 0 {
+            problems.push("pool draw must be at least 1".to_string());
+        }
+        if self.draw > bank_size {
+            problems.push(format!(
+                "pool draws {} questions but the quiz only has {bank_size}",
+                self.draw
+            ));
+        }
+        problems
+    }
+
+    /// Picks the questions for one attempt, they keep their order in the bank.
+    pub fn draw(&self, bank: Vec<Question>, rng: &mut impl Rng) -> Vec<Question> {
+        let draw = self.draw.min(bank.len());
+        let mut groups: Vec<(Option<String>, Vec<usize>)> = Vec::new();
+        for (index, question) in bank.iter().enumerate() {
+            let key = self
+                .stratify_by
+                .and_then(|stratify| question.stratum(stratify));
+            match groups.iter_mut().find(|(group_key, _)| *group_key == key) {
+                Some((_, indexes)) => indexes.push(index),
+                None => groups.push((key, vec![index])),
+            }
+        }
+
+        // every group gets its whole share, the questions left over go to the largest remainders
+        let mut shares: Vec<(usize, usize)> = groups
+            .iter()
+            .map(|(_, indexes)| {
+                (
+                    indexes.len() * draw / bank.len(),
+                    indexes.len() * draw % bank.len(),
+                )
+            })
+            .collect();
+        let mut left_over = draw - shares.iter().map(|(share, _)| share).sum::<usize>();
+        let mut by_remainder: Vec<usize> = (0..groups.len()).collect();
+        by_remainder.shuffle(rng);
+        by_remainder.sort_by_key(|group| std::cmp::Reverse(shares[*group].1));
+        for group in by_remainder {
+            if left_over == 0 {
+                break;
+            }
+            shares[group].0 += 1;
+            left_over -= 1;
+        }
+
+        let mut drawn: Vec<usize> = Vec::new();
+        for ((_, indexes), (share, _)) in groups.iter().zip(&shares) {
+            drawn.extend(indexes.choose_multiple(rng, *share));
+        }
+        drawn.sort();
+        bank.into_iter()
+            .enumerate()
+            .filter(|(index, _)| drawn.contains(index))
+            .map(|(_, question)| question)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// a question per (tags, difficulty), named by its place in the bank.
+    fn bank(questions: &[(&[&str], &str)]) -> Vec<Question> {
+        questions
+            .iter()
+            .enumerate()
+            .map(|(index, (tags, difficulty))| {
+                toml::from_str(&format!(
+                    r#"
+                    question = "{index}"
+                    answers = ["a", "b"]
+                    correct_answer = 1
+                    tags = {tags:?}
+                    difficulty = "{difficulty}"
+                    "#
+                ))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn pool(draw: usize, stratify_by: Option<Stratify>) -> Pool {
+        Pool { draw, stratify_by }
+    }
+
+    /// how many drawn questions each key has, sorted by key.
+    fn counts(drawn: &[Question], stratify: Stratify) -> Vec<(Option<String>, usize)> {
+        let mut counts: Vec<(Option<String>, usize)> = Vec::new();
+        for question in drawn {
+            let key = question.stratum(stratify);
+            match counts.iter_mut().find(|(counted, _)| *counted == key) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((key, 1)),
+            }
+        }
+        counts.sort();
+        counts
+    }
+
+    fn key(key: &str) -> Option<String> {
+        Some(key.to_string())
+    }
+
+    /// five net, three storage and two cpu questions.
+    fn tagged_bank() -> Vec<Question> {
+        let mut questions: Vec<(&[&str], &str)> = vec![(&["net"], "easy"); 5];
+        questions.extend([(&["Storage"] as &[&str], "hard"); 3]);
+        questions.extend([(&["cpu"] as &[&str], "easy"); 2]);
+        bank(&questions)
+    }
+
+    #[test]
+    fn stratified_draws_add_up_to_the_draw_size() {
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let drawn = pool(5, Some(Stratify::Tag)).draw(tagged_bank(), &mut rng);
+            assert_eq!(drawn.len(), 5);
+            // net and storage both have half a question left over and share the last place
+            let counts = counts(&drawn, Stratify::Tag);
+            assert!(
+                counts == vec![(key("cpu"), 1), (key("net"), 3), (key("storage"), 1)]
+                    || counts == vec![(key("cpu"), 1), (key("net"), 2), (key("storage"), 2)],
+                "{counts:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn small_strata_never_give_more_than_they_have() {
+        let mut questions: Vec<(&[&str], &str)> = vec![(&["common"], "easy"); 9];
+        questions.push((&["rare"], "easy"));
+        let mut rare_drawn = 0;
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let drawn = pool(5, Some(Stratify::Tag)).draw(bank(&questions), &mut rng);
+            assert_eq!(drawn.len(), 5);
+            let rare = drawn
+                .iter()
+                .filter(|question| question.stratum(Stratify::Tag) == key("rare"))
+                .count();
+            assert!(rare <= 1);
+            rare_drawn += rare;
+        }
+        // the rare question has as big a remainder as the common ones, so it is drawn now and then
+        assert!(rare_drawn > 0);
+    }
+
+    #[test]
+    fn draws_larger_than_the_bank_give_the_whole_bank() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let drawn = pool(50, Some(Stratify::Tag)).draw(tagged_bank(), &mut rng);
+        assert_eq!(drawn, tagged_bank());
+        assert_eq!(
+            pool(50, None).problems(10),
+            vec!["pool draws 50 questions but the quiz only has 10"]
+        );
+        assert_eq!(
+            pool(0, None).problems(10),
+            vec!["pool draw must be at least 1"]
+        );
+    }
+
+    #[test]
+    fn stratifies_by_the_first_tag_or_by_difficulty() {
+        let bank = bank(&[
+            (&["net", "storage"], "easy"),
+            (&["net"], "hard"),
+            (&["storage", "net"], "easy"),
+            (&["storage"], "hard"),
+        ]);
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let by_tag = pool(2, Some(Stratify::Tag)).draw(bank.clone(), &mut rng);
+            assert_eq!(
+                counts(&by_tag, Stratify::Tag),
+                vec![(key("net"), 1), (key("storage"), 1)]
+            );
+            let by_difficulty = pool(2, Some(Stratify::Difficulty)).draw(bank.clone(), &mut rng);
+            assert_eq!(
+                counts(&by_difficulty, Stratify::Difficulty),
+                vec![(key("easy"), 1), (key("hard"), 1)]
+            );
+        }
+    }
+
+    #[test]
+    fn the_same_seed_draws_the_same_questions_in_bank_order() {
+        let draw = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            pool(4, None)
+                .draw(tagged_bank(), &mut rng)
+                .into_iter()
+                .map(|question| question.question.parse().unwrap())
+                .collect::<Vec<usize>>()
+        };
+        assert_eq!(draw(3), draw(3));
+        let drawn = draw(3);
+        assert!(drawn.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!((0..20).any(|seed| draw(seed) != drawn));
+    }
+}
@@ -0,0 +1,1882 @@
+use colored::{ColoredString, Colorize};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::markdown;
+use crate::pool::Stratify;
+use crate::template::Template;
+use crate::text_match;
+use crate::tools;
+
+/// user input that leaves a question unanswered, it scores zero and is never penalized
+pub const SKIP: &str = "skip";
+
+/// How a question expects to be answered.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestionKind {
+    /// pick the single correct answer
+    #[default]
+    MultipleChoice,
+    /// pick every correct answer out of several
+    MultiSelect,
+    /// type the answer, matched against a list of accepted answers
+    FreeText,
+    /// type a number, right when close enough to the answer or inside a range
+    Numeric,
+    /// put shuffled answers back in the order they are listed in
+    Ordering,
+    /// match every item of one shuffled column to its partner in the other
+    Matching,
+    /// type the missing words marked like {{1}} in the question text
+    Cloze,
+}
+
+/// How points are given for questions that can be partly right.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Scoring {
+    /// the full point only for a completely right answer
+    #[default]
+    #[serde(alias = "exact")]
+    AllOrNothing,
+    /// a share of the point for every right pick, wrong picks take a share away.
+    /// for ordering questions a share for every item in its right position
+    #[serde(alias = "positions")]
+    Partial,
+    /// ordering questions only, a share for every pair of items in the right order
+    KendallTau,
+}
+
+/// One answer of a choice question, either just its text or a table with extra details.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum AnswerOption {
+    Text(String),
+    Detailed {
+        text: String,
+        /// why this answer is right or wrong, shown to players who picked it
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rationale: Option<String>,
+        /// keeps the answer in one place while the others are shuffled
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pinned: Option<Pin>,
+    },
+}
+
+/// Where a pinned answer is shown, first, last or at a position counted from 1.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(untagged)]
+pub enum Pin {
+    Position(usize),
+    Place(PinPlace),
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PinPlace {
+    First,
+    Last,
+}
+
+impl Pin {
+    /// the display index the pin stands for among the given number of answers.
+    fn index(self, answer_count: usize) -> usize {
+        match self {
+            Pin::Position(position) => position.saturating_sub(1),
+            Pin::Place(PinPlace::First) => 0,
+            Pin::Place(PinPlace::Last) => answer_count.saturating_sub(1),
+        }
+    }
+}
+
+impl AnswerOption {
+    pub fn text(&self) -> &str {
+        match self {
+            AnswerOption::Text(text) | AnswerOption::Detailed { text, .. } => text,
+        }
+    }
+
+    pub fn rationale(&self) -> Option<&str> {
+        match self {
+            AnswerOption::Text(_) => None,
+            AnswerOption::Detailed { rationale, .. } => rationale.as_deref(),
+        }
+    }
+
+    pub fn pinned(&self) -> Option<Pin> {
+        match self {
+            AnswerOption::Text(_) => None,
+            AnswerOption::Detailed { pinned, .. } => *pinned,
+        }
+    }
+}
+
+impl fmt::Display for AnswerOption {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Question {
+    pub question: String,
+    #[serde(default, skip_serializing_if = "is_default_kind")]
+    pub kind: QuestionKind,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub answers: Vec<AnswerOption>,
+    /// indexed from 1, used by multiple choice questions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correct_answer: Option<usize>,
+    /// indexed from 1, used by multi select questions
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub correct_answers: Vec<usize>,
+    #[serde(default, skip_serializing_if = "is_default_scoring")]
+    pub scoring: Scoring,
+    /// used by free text questions, the first one is shown as the correct answer
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accepted_answers: Vec<String>,
+    /// typing mistakes forgiven in free text answers, counted as single character edits.
+    /// answers shorter than four characters must be exact, longer ones allow one typo per four characters
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub max_typos: usize,
+    /// used by numeric questions together with one of the tolerances
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub numeric_answer: Option<f64>,
+    /// item and partner for matching questions, like term and definition
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pairs: Vec<[String; 2]>,
+    /// accepted answers of every blank in a cloze question, in blank number order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blanks: Vec<Vec<String>>,
+    /// numeric answers this far off the answer either way still count
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tolerance: Option<f64>,
+    /// like tolerance, as a percentage of the answer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tolerance_percent: Option<f64>,
+    /// inclusive lowest and highest accepted number, instead of numeric_answer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<[f64; 2]>,
+    /// shown after numeric answers, players may type it after their number
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    /// what a completely right answer is worth
+    #[serde(default = "default_points", skip_serializing_if = "is_default_points")]
+    pub points: f32,
+    /// false shows the answers in file order, pinned answers are kept in place either way
+    #[serde(
+        default = "default_shuffle",
+        skip_serializing_if = "is_default_shuffle"
+    )]
+    pub shuffle: bool,
+    /// generates the question text and answer from drawn numbers on every attempt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<Template>,
+    /// names the question so next_on_correct and next_on_wrong can lead to it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// id of the question asked after a correct answer, "end" ends the quiz.
+    /// without it the next question in the file follows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_on_correct: Option<String>,
+    /// id of the question asked after a wrong, partly right, skipped or timed out answer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_on_wrong: Option<String>,
+    /// name of the section the question is listed in, set on loading
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// topics the question belongs to, used to stratify question pools
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// free form, like easy, medium or hard
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<String>,
+    /// teaches why the correct answer is correct, shown in review and optionally after answering
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+    /// seconds to answer, the answer is wrong when time runs out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_limit: Option<u64>,
+}
+
+fn is_default_kind(kind: &QuestionKind) -> bool {
+    *kind == QuestionKind::default()
+}
+
+fn is_default_scoring(scoring: &Scoring) -> bool {
+    *scoring == Scoring::default()
+}
+
+fn is_zero(number: &usize) -> bool {
+    *number == 0
+}
+
+fn default_shuffle() -> bool {
+    true
+}
+
+fn is_default_shuffle(shuffle: &bool) -> bool {
+    *shuffle == default_shuffle()
+}
+
+fn default_points() -> f32 {
+    1.0
+}
+
+fn is_default_points(points: &f32) -> bool {
+    *points == default_points()
+}
+
+/// A question together with what the user answered and the points it earned.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct UserAnswer {
+    pub question: Question,
+    pub answer: String,
+    pub points: f32,
+    /// indexes into the question answers the user picked, empty for typed answers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub picked: Vec<usize>,
+    /// what the user typed into each blank of a cloze question
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blank_answers: Vec<String>,
+    /// time from showing the question to the answer
+    #[serde(default)]
+    pub seconds_taken: f32,
+    #[serde(default)]
+    pub timed_out: bool,
+    #[serde(default)]
+    pub skipped: bool,
+}
+
+/// What came back from waiting on the user for an answer.
+enum Reply {
+    Answer(String),
+    TimeUp,
+    Skip,
+}
+
+impl UserAnswer {
+    /// Scores an answer from a save written before answers kept their points,
+    /// those saves only hold multiple choice questions and the text of the picked answer.
+    pub fn from_legacy(question: Question, answer: String) -> UserAnswer {
+        match question
+            .answers
+            .iter()
+            .position(|option| option.text() == answer)
+        {
+            Some(index) => question.answered(&[index]),
+            None => UserAnswer::new(&question, answer, 0.0),
+        }
+    }
+
+    fn new(question: &Question, answer: String, points: f32) -> UserAnswer {
+        UserAnswer {
+            question: question.clone(),
+            answer,
+            points,
+            picked: Vec::new(),
+            blank_answers: Vec::new(),
+            seconds_taken: 0.0,
+            timed_out: false,
+            skipped: false,
+        }
+    }
+
+    /// true when the answer earned every point the question is worth.
+    pub fn is_correct(&self) -> bool {
+        self.points >= self.question.max_points()
+    }
+
+    /// prints a one word verdict, correct, partly right, wrong or skipped.
+    pub fn print_verdict(&self) {
+        if self.skipped {
+            println!("{}", "Skipped.".yellow());
+        } else if self.is_correct() {
+            println!("{}", "Correct!".green());
+        } else if self.points > 0.0 {
+            println!(
+                "{}",
+                format!(
+                    "Partly right, {} points.",
+                    tools::format_points(self.points)
+                )
+                .yellow()
+            );
+        } else if self.points < 0.0 {
+            println!(
+                "{}",
+                format!("Wrong! {} points.", tools::format_points(self.points)).red()
+            );
+        } else {
+            println!("{}", "Wrong!".red());
+        }
+    }
+
+    /// "Your answer:" colored by how right the answer was, with the points unless it earned all or nothing.
+    fn answer_label(&self) -> ColoredString {
+        if self.is_correct() {
+            return "Your answer:".green();
+        }
+        if self.points == 0.0 {
+            return "Your answer:".red();
+        }
+        let label = format!(
+            "Your answer ({} points):",
+            tools::format_points(self.points)
+        );
+        if self.points > 0.0 {
+            label.yellow()
+        } else {
+            label.red()
+        }
+    }
+
+    /// prints the users answer colored by how right it was, and the correct answer if it was not.
+    pub fn print_report(&self) {
+        if self.question.kind == QuestionKind::Cloze {
+            self.print_cloze_report();
+            return;
+        }
+        // answers and correct answers can hold markdown like the answers they were picked from
+        let label = self.answer_label();
+        let answer = markdown::render(&self.answer, label.chars().count() + 1);
+        if self.is_correct() {
+            println!("{label} {}", answer.green());
+            // typed answers can be accepted with typos or as an alternative spelling
+            if let Some(accepted) = self.question.matched_answer(&self.answer) {
+                if accepted != self.answer {
+                    println!("{} {}", "Accepted as:".green(), accepted.green());
+                }
+            }
+            return;
+        }
+
+        let answer = if self.points > 0.0 {
+            answer.yellow()
+        } else {
+            answer.red()
+        };
+        println!("{label} {answer}");
+        let correct_label = "Correct Answer:";
+        let correct_answer = markdown::render(
+            &self.question.correct_answer_text(),
+            correct_label.len() + 1,
+        );
+        println!("{} {}", correct_label.green(), correct_answer.green());
+    }
+
+    /// prints the sentence as the user completed it, every blank green when right and red when wrong.
+    fn print_cloze_report(&self) {
+        let completed = self.question.fill_blanks(|blank| {
+            let typed = self
+                .blank_answers
+                .get(blank)
+                .map(String::as_str)
+                .unwrap_or("____");
+            match self.question.matched_blank(blank, typed) {
+                Some(_) => typed.green().to_string(),
+                None => typed.red().to_string(),
+            }
+        });
+        println!("{} {completed}", self.answer_label());
+        if self.is_correct() {
+            return;
+        }
+        println!(
+            "{} {}",
+            "Correct Answer:".green(),
+            self.question.correct_answer_text().green()
+        );
+    }
+
+    /// prints the question explanation and the rationale of every picked answer, if the quiz has them.
+    pub fn print_explanation(&self) {
+        // every item of an ordering question is picked, only choices explain themselves
+        let explains_picks = matches!(
+            self.question.kind,
+            QuestionKind::MultipleChoice | QuestionKind::MultiSelect
+        );
+        for index in self.picked.iter().filter(|_| explains_picks) {
+            let answer = &self.question.answers[*index];
+            if let Some(rationale) = answer.rationale() {
+                let label = format!("'{answer}':");
+                let indent = label.chars().count() + 1;
+                println!("{} {}", label.cyan(), markdown::render(rationale, indent));
+            }
+        }
+        if let Some(explanation) = &self.question.explanation {
+            let label = "Explanation:";
+            let indent = label.len() + 1;
+            println!("{} {}", label.cyan(), markdown::render(explanation, indent));
+        }
+    }
+}
+
+impl Question {
+    const MIN_ANSWERS: usize = 2;
+    /// labels of the second column of a matching question
+    const MATCH_LETTERS: &'static str = "abcdefghijklmnopqrstuvwxyz";
+
+    /// Lists everything wrong with the question, empty when it can be asked.
+    pub fn problems(&self) -> Vec<String> {
+        // a template is checked through the questions it generates
+        if let Some(template) = &self.template {
+            return template.problems(self);
+        }
+        let mut problems: Vec<String> = Vec::new();
+        if self.question.trim().is_empty() {
+            problems.push("question text is empty".to_string());
+        }
+        if self.is_choice() && self.answers.len() < Question::MIN_ANSWERS {
+            problems.push(format!(
+                "needs at least {} answers, found {}",
+                Question::MIN_ANSWERS,
+                self.answers.len()
+            ));
+        }
+        // answers are shown by text, duplicates could not be told apart
+        for (index, answer) in self.answers.iter().enumerate() {
+            if self.answers[..index]
+                .iter()
+                .any(|earlier| earlier.text() == answer.text())
+            {
+                problems.push(format!("answer '{answer}' is listed more than once"));
+            }
+        }
+
+        problems.extend(self.pin_problems());
+
+        if self.time_limit == Some(0) {
+            problems.push("time_limit must be at least 1 second".to_string());
+        }
+        if !(self.points.is_finite() && self.points > 0.0) {
+            problems.push(format!("points is {} but must be above 0", self.points));
+        }
+
+        match self.kind {
+            QuestionKind::MultipleChoice => match self.correct_answer {
+                None => problems.push("correct_answer is missing".to_string()),
+                Some(correct_answer) if !self.is_answer_number(correct_answer) => {
+                    problems.push(format!(
+                        "correct_answer is {} but must be between 1 and {}",
+                        correct_answer,
+                        self.answers.len()
+                    ))
+                }
+                Some(_) => (),
+            },
+            QuestionKind::MultiSelect => {
+                if self.correct_answers.is_empty() {
+                    problems.push("correct_answers is missing".to_string());
+                }
+                for (index, correct_answer) in self.correct_answers.iter().enumerate() {
+                    if !self.is_answer_number(*correct_answer) {
+                        problems.push(format!(
+                            "correct_answers has {} but must be between 1 and {}",
+                            correct_answer,
+                            self.answers.len()
+                        ));
+                    }
+                    if self.correct_answers[..index].contains(correct_answer) {
+                        problems.push(format!(
+                            "correct_answers lists {correct_answer} more than once"
+                        ));
+                    }
+                }
+            }
+            QuestionKind::FreeText => {
+                if self.accepted_answers.is_empty() {
+                    problems.push("accepted_answers is missing".to_string());
+                }
+                if self
+                    .accepted_answers
+                    .iter()
+                    .any(|answer| text_match::normalize(answer).is_empty())
+                {
+                    problems.push("accepted_answers has an empty answer".to_string());
+                }
+                if !self.answers.is_empty() {
+                    problems
+                        .push("free text questions use accepted_answers, not answers".to_string());
+                }
+            }
+            QuestionKind::Numeric => problems.extend(self.numeric_problems()),
+            QuestionKind::Matching => problems.extend(self.matching_problems()),
+            QuestionKind::Cloze => problems.extend(self.cloze_problems()),
+            // the answers are listed in the correct order
+            QuestionKind::Ordering => (),
+        }
+        if self.scoring == Scoring::KendallTau && self.kind == QuestionKind::MultiSelect {
+            problems.push("kendall_tau scoring only works for ordering questions".to_string());
+        }
+        problems.extend(self.unused_field_problems());
+        problems
+    }
+
+    /// fields that are set but only used by another kind of question, likely a mistaken kind.
+    fn unused_field_problems(&self) -> Vec<String> {
+        use QuestionKind::*;
+        let fields: [(&str, bool, &[QuestionKind], &str); 12] = [
+            (
+                "correct_answer",
+                self.correct_answer.is_some(),
+                &[MultipleChoice],
+                "multiple_choice",
+            ),
+            (
+                "correct_answers",
+                !self.correct_answers.is_empty(),
+                &[MultiSelect],
+                "multi_select",
+            ),
+            (
+                "scoring",
+                self.scoring != Scoring::default(),
+                &[MultiSelect, Ordering],
+                "multi_select and ordering",
+            ),
+            (
+                "accepted_answers",
+                !self.accepted_answers.is_empty(),
+                &[FreeText],
+                "free_text",
+            ),
+            (
+                "max_typos",
+                self.max_typos > 0,
+                &[FreeText, Cloze],
+                "free_text and cloze",
+            ),
+            (
+                "numeric_answer",
+                self.numeric_answer.is_some(),
+                &[Numeric],
+                "numeric",
+            ),
+            ("range", self.range.is_some(), &[Numeric], "numeric"),
+            ("tolerance", self.tolerance.is_some(), &[Numeric], "numeric"),
+            (
+                "tolerance_percent",
+                self.tolerance_percent.is_some(),
+                &[Numeric],
+                "numeric",
+            ),
+            ("unit", self.unit.is_some(), &[Numeric], "numeric"),
+            ("pairs", !self.pairs.is_empty(), &[Matching], "matching"),
+            ("blanks", !self.blanks.is_empty(), &[Cloze], "cloze"),
+        ];
+        fields
+            .iter()
+            .filter(|(_, is_set, used_by, _)| *is_set && !used_by.contains(&self.kind))
+            .map(|(field, _, _, kinds)| format!("{field} only works for {kinds} questions"))
+            .collect()
+    }
+
+    fn pin_problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = Vec::new();
+        let pins_answers = self.answers.iter().any(|answer| answer.pinned().is_some());
+        // a fixed layout would give the answer away
+        if matches!(self.kind, QuestionKind::Ordering | QuestionKind::Matching)
+            && (pins_answers || !self.shuffle)
+        {
+            problems.push(
+                "ordering and matching questions are always shuffled, they cannot pin answers or set shuffle = false"
+                    .to_string(),
+            );
+            return problems;
+        }
+        let mut taken: Vec<usize> = Vec::new();
+        for answer in &self.answers {
+            let Some(pin) = answer.pinned() else {
+                continue;
+            };
+            if let Pin::Position(position) = pin {
+                if !self.is_answer_number(position) {
+                    problems.push(format!(
+                        "answer '{answer}' is pinned to {position} but must be between 1 and {}",
+                        self.answers.len()
+                    ));
+                    continue;
+                }
+            }
+            let index = pin.index(self.answers.len());
+            if taken.contains(&index) {
+                problems.push(format!(
+                    "answer '{answer}' is pinned to position {} like another answer",
+                    index + 1
+                ));
+            }
+            taken.push(index);
+        }
+        problems
+    }
+
+    fn matching_problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = Vec::new();
+        if self.pairs.len() < Question::MIN_ANSWERS {
+            problems.push(format!(
+                "needs at least {} pairs, found {}",
+                Question::MIN_ANSWERS,
+                self.pairs.len()
+            ));
+        }
+        if self.pairs.len() > Question::MATCH_LETTERS.len() {
+            problems.push(format!(
+                "has {} pairs but at most {} can be matched by letter",
+                self.pairs.len(),
+                Question::MATCH_LETTERS.len()
+            ));
+        }
+        for (index, pair) in self.pairs.iter().enumerate() {
+            if pair.iter().any(|item| item.trim().is_empty()) {
+                problems.push(format!("pair {} has an empty item", index + 1));
+            }
+            // each column is shown on its own, duplicates could not be told apart
+            for column in 0..2 {
+                if self.pairs[..index]
+                    .iter()
+                    .any(|earlier| earlier[column] == pair[column])
+                {
+                    problems.push(format!("'{}' is listed more than once", pair[column]));
+                }
+            }
+        }
+        if !self.answers.is_empty() {
+            problems.push("matching questions use pairs, not answers".to_string());
+        }
+        problems
+    }
+
+    fn cloze_problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = Vec::new();
+        if self.blanks.is_empty() {
+            problems.push("blanks is missing".to_string());
+        }
+        for text in self.marker_texts() {
+            if let Ok(number) = text.trim().parse::<usize>() {
+                if number.to_string() != text {
+                    problems.push(format!(
+                        "blank marker {{{{{text}}}}} must be written as {{{{{number}}}}}"
+                    ));
+                }
+            }
+        }
+        let markers = self.blank_markers();
+        for number in 1..=self.blanks.len() {
+            match markers.iter().filter(|marker| **marker == number).count() {
+                0 => problems.push(format!("question text has no {{{{{number}}}}} blank")),
+                1 => (),
+                _ => problems.push(format!("question text has {{{{{number}}}}} more than once")),
+            }
+        }
+        for marker in markers {
+            if marker == 0 || marker > self.blanks.len() {
+                problems.push(format!(
+                    "question text has {{{{{marker}}}}} but blanks only has {} entries",
+                    self.blanks.len()
+                ));
+            }
+        }
+        for (index, accepted) in self.blanks.iter().enumerate() {
+            if accepted
+                .iter()
+                .all(|answer| text_match::normalize(answer).is_empty())
+            {
+                problems.push(format!("blank {} has no accepted answer", index + 1));
+            }
+        }
+        if !self.answers.is_empty() {
+            problems.push("cloze questions use blanks, not answers".to_string());
+        }
+        problems
+    }
+
+    /// numbers of every {{n}} blank marker in the question text, in order of appearance.
+    /// only markers written exactly like fill_blanks replaces them count, so no spaces or leading zeros
+    fn blank_markers(&self) -> Vec<usize> {
+        self.marker_texts()
+            .into_iter()
+            .filter_map(|text| {
+                text.parse::<usize>()
+                    .ok()
+                    .filter(|number| number.to_string() == text)
+            })
+            .collect()
+    }
+
+    /// text between every {{ and }} in the question text, in order of appearance.
+    fn marker_texts(&self) -> Vec<&str> {
+        let mut texts: Vec<&str> = Vec::new();
+        let mut rest = self.question.as_str();
+        while let Some(start) = rest.find("{{") {
+            rest = &rest[start + 2..];
+            if let Some(end) = rest.find("}}") {
+                texts.push(&rest[..end]);
+            }
+        }
+        texts
+    }
+
+    /// the question text with every blank marker replaced by the text for that blank, counted from 0.
+    fn fill_blanks(&self, fill: impl Fn(usize) -> String) -> String {
+        let mut text = self.question.clone();
+        for blank in 0..self.blanks.len() {
+            text = text.replace(&format!("{{{{{}}}}}", blank + 1), &fill(blank));
+        }
+        text
+    }
+
+    /// the group a stratified pool puts the question in, none when it has no tag or difficulty.
+    pub fn stratum(&self, stratify: Stratify) -> Option<String> {
+        match stratify {
+            Stratify::Tag => self.tags.first(),
+            Stratify::Difficulty => self.difficulty.as_ref(),
+        }
+        .map(|key| key.trim().to_lowercase())
+    }
+
+    /// the question as shown to players, cloze blanks as numbered gaps.
+    pub fn prompt_text(&self) -> String {
+        match self.kind {
+            QuestionKind::Cloze => self.fill_blanks(|blank| format!("____({})", blank + 1)),
+            _ => self.question.clone(),
+        }
+    }
+
+    fn numeric_problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = Vec::new();
+        match (self.numeric_answer, self.range) {
+            (None, None) => problems.push("numeric_answer or range is missing".to_string()),
+            (Some(_), Some(_)) => {
+                problems.push("use either numeric_answer or range, not both".to_string())
+            }
+            (None, Some([lowest, highest])) if lowest > highest => problems.push(format!(
+                "range starts at {lowest} which is above its end {highest}"
+            )),
+            _ => (),
+        }
+        if self.tolerance.is_some() && self.tolerance_percent.is_some() {
+            problems.push("use either tolerance or tolerance_percent, not both".to_string());
+        }
+        if self.range.is_some() && (self.tolerance.is_some() || self.tolerance_percent.is_some()) {
+            problems.push("a range can not have a tolerance".to_string());
+        }
+        if self.tolerance.is_some_and(|tolerance| tolerance < 0.0)
+            || self
+                .tolerance_percent
+                .is_some_and(|tolerance| tolerance < 0.0)
+        {
+            problems.push("tolerance can not be negative".to_string());
+        }
+        if !self.answers.is_empty() {
+            problems.push("numeric questions use numeric_answer or range, not answers".to_string());
+        }
+        problems
+    }
+
+    fn is_answer_number(&self, number: usize) -> bool {
+        (1..=self.answers.len()).contains(&number)
+    }
+
+    /// true for questions answered by picking from the answers list.
+    fn is_choice(&self) -> bool {
+        matches!(
+            self.kind,
+            QuestionKind::MultipleChoice | QuestionKind::MultiSelect | QuestionKind::Ordering
+        )
+    }
+
+    /// most points the question can give.
+    pub fn max_points(&self) -> f32 {
+        self.points
+    }
+
+    /// Prompts the user for an answer, returns none if the user enters the given exit command.
+    pub fn ask(&self, exit_command: &str) -> Option<UserAnswer> {
+        self.ask_before(exit_command, None, None)
+    }
+
+    /// Like ask, the question also ends at the quiz deadline if that comes before its own time limit.
+    /// The same layout seed always shuffles the answers into the same order.
+    pub fn ask_before(
+        &self,
+        exit_command: &str,
+        quiz_deadline: Option<Instant>,
+        layout_seed: Option<u64>,
+    ) -> Option<UserAnswer> {
+        let started = Instant::now();
+        let question_deadline = self
+            .time_limit
+            .map(|time_limit| started + Duration::from_secs(time_limit));
+        let deadline = match (question_deadline, quiz_deadline) {
+            (Some(question_deadline), Some(quiz_deadline)) => {
+                Some(question_deadline.min(quiz_deadline))
+            }
+            (question_deadline, quiz_deadline) => question_deadline.or(quiz_deadline),
+        };
+
+        println!("{}", markdown::render(&self.prompt_text(), 0));
+        let mut user_answer = match self.kind {
+            QuestionKind::MultipleChoice | QuestionKind::MultiSelect | QuestionKind::Ordering => {
+                self.ask_choice(exit_command, deadline, layout_seed)
+            }
+            QuestionKind::FreeText => self.ask_free_text(exit_command, deadline),
+            QuestionKind::Numeric => self.ask_numeric(exit_command, deadline),
+            QuestionKind::Matching => self.ask_matching(exit_command, deadline, layout_seed),
+            QuestionKind::Cloze => self.ask_cloze(exit_command, deadline),
+        }?;
+        user_answer.seconds_taken = started.elapsed().as_secs_f32();
+
+        if user_answer.timed_out {
+            // also swallows anything half typed when time ran out
+            println!("\nTime is up! Press enter to continue.");
+            tools::read_input();
+        }
+        Some(user_answer)
+    }
+
+    /// reads a line of user input as typed, none if it is the exit command.
+    fn read_answer(exit_command: &str, deadline: Option<Instant>) -> Option<Reply> {
+        let user_input = match deadline {
+            Some(deadline) => match tools::read_raw_input_until(deadline) {
+                Some(user_input) => user_input,
+                None => return Some(Reply::TimeUp),
+            },
+            None => tools::read_raw_input(),
+        };
+        if user_input.to_lowercase() == exit_command {
+            return None; // begin generating save file or leave the game.
+        }
+        if user_input.to_lowercase() == SKIP {
+            return Some(Reply::Skip);
+        }
+        Some(Reply::Answer(user_input))
+    }
+
+    fn print_commands(exit_command: &str) {
+        println!("Type '{SKIP}' to leave the question unanswered.");
+        println!("Type '{exit_command}' if you would like to do so.");
+    }
+
+    /// answer for a question the user chose to skip.
+    fn skipped(&self) -> UserAnswer {
+        let mut user_answer = UserAnswer::new(self, "skipped".to_string(), 0.0);
+        user_answer.skipped = true;
+        user_answer
+    }
+
+    /// answer for a question that was not answered in time.
+    pub fn timed_out(&self) -> UserAnswer {
+        let mut user_answer = UserAnswer::new(self, "no answer, time ran out".to_string(), 0.0);
+        user_answer.timed_out = true;
+        user_answer
+    }
+
+    /// indexes into answers in the order they are shown, pinned answers at their place
+    /// and the rest shuffled around them unless the question turns shuffling off.
+    fn display_order(&self, rng: &mut ChaCha8Rng) -> Vec<usize> {
+        let answer_count = self.answers.len();
+        let mut slots: Vec<Option<usize>> = vec![None; answer_count];
+        let mut free: Vec<usize> = Vec::new();
+        for (index, answer) in self.answers.iter().enumerate() {
+            match answer.pinned() {
+                Some(pin) => slots[pin.index(answer_count)] = Some(index),
+                None => free.push(index),
+            }
+        }
+        if self.shuffle {
+            free.shuffle(rng);
+        }
+        let mut free = free.into_iter();
+        slots
+            .into_iter()
+            .map(|slot| {
+                slot.or_else(|| free.next())
+                    .expect("validation keeps a slot per answer")
+            })
+            .collect()
+    }
+
+    /// seeded when the layout has to be reproducible, random otherwise.
+    /// resumed saves rebuild their layout from the seed, so the rng must give the same numbers
+    /// in every rand version, which StdRng does not promise
+    fn layout_rng(layout_seed: Option<u64>) -> ChaCha8Rng {
+        match layout_seed {
+            Some(layout_seed) => ChaCha8Rng::seed_from_u64(layout_seed),
+            None => ChaCha8Rng::from_entropy(),
+        }
+    }
+
+    fn ask_choice(
+        &self,
+        exit_command: &str,
+        deadline: Option<Instant>,
+        layout_seed: Option<u64>,
+    ) -> Option<UserAnswer> {
+        let mut rng = Question::layout_rng(layout_seed);
+
+        // original answer vector will act as answer key, only the display order is shuffled
+        let correct_order: Vec<usize> = (0..self.answers.len()).collect();
+        let mut shuffled_order = self.display_order(&mut rng);
+        // an ordering question shown in its correct order would give the answer away
+        while self.kind == QuestionKind::Ordering && shuffled_order == correct_order {
+            shuffled_order = self.display_order(&mut rng);
+        }
+
+        for (number, answer_index) in shuffled_order.iter().enumerate() {
+            let label = format!("[{}]", number + 1);
+            let answer = self.answers[*answer_index].to_string();
+            println!("{label} {}", markdown::render(&answer, label.len() + 1));
+        }
+        match self.kind {
+            QuestionKind::MultiSelect => println!(
+                "More than one answer is correct, enter the numbers of every answer you beleive is correct, like 1,3."
+            ),
+            QuestionKind::Ordering => println!(
+                "Enter the numbers of all {} items in the correct order, like 3 1 2.",
+                self.answers.len()
+            ),
+            _ => println!("Enter the number next to the answer you beleive is correct."),
+        }
+        Question::print_commands(exit_command);
+        loop {
+            let user_input = match Question::read_answer(exit_command, deadline)? {
+                Reply::Answer(user_input) => user_input,
+                Reply::TimeUp => return Some(self.timed_out()),
+                Reply::Skip => return Some(self.skipped()),
+            };
+            let picked_numbers = match self.read_picks(&user_input) {
+                Ok(picked_numbers) => picked_numbers,
+                Err(message) => {
+                    println!("{message}");
+                    continue;
+                }
+            };
+            println!();
+            let picked: Vec<usize> = picked_numbers
+                .iter()
+                .map(|number| shuffled_order[number - 1])
+                .collect();
+            return Some(self.answered(&picked));
+        }
+    }
+
+    fn ask_free_text(&self, exit_command: &str, deadline: Option<Instant>) -> Option<UserAnswer> {
+        println!("Type your answer.");
+        Question::print_commands(exit_command);
+        loop {
+            let user_input = match Question::read_answer(exit_command, deadline)? {
+                Reply::Answer(user_input) => user_input,
+                Reply::TimeUp => return Some(self.timed_out()),
+                Reply::Skip => return Some(self.skipped()),
+            };
+            if text_match::normalize(&user_input).is_empty() {
+                println!("Please type an answer.");
+                continue;
+            }
+            println!();
+            let points = match self.matched_answer(&user_input) {
+                Some(_) => self.max_points(),
+                None => 0.0,
+            };
+            return Some(UserAnswer::new(self, user_input, points));
+        }
+    }
+
+    fn ask_cloze(&self, exit_command: &str, deadline: Option<Instant>) -> Option<UserAnswer> {
+        println!("Type the missing word of each blank.");
+        Question::print_commands(exit_command);
+        let mut blank_answers: Vec<String> = Vec::new();
+        for blank in 0..self.blanks.len() {
+            println!("Blank {}:", blank + 1);
+            loop {
+                let user_input = match Question::read_answer(exit_command, deadline)? {
+                    Reply::Answer(user_input) => user_input,
+                    Reply::TimeUp => return Some(self.timed_out()),
+                    Reply::Skip => return Some(self.skipped()),
+                };
+                if text_match::normalize(&user_input).is_empty() {
+                    println!("Please type an answer.");
+                    continue;
+                }
+                blank_answers.push(user_input);
+                break;
+            }
+        }
+        println!();
+        let right_blanks = blank_answers
+            .iter()
+            .enumerate()
+            .filter(|(blank, typed)| self.matched_blank(*blank, typed).is_some())
+            .count();
+        let points = right_blanks as f32 / self.blanks.len() as f32 * self.max_points();
+        let mut user_answer = UserAnswer::new(self, blank_answers.join(", "), points);
+        user_answer.blank_answers = blank_answers;
+        Some(user_answer)
+    }
+
+    fn ask_numeric(&self, exit_command: &str, deadline: Option<Instant>) -> Option<UserAnswer> {
+        match &self.unit {
+            Some(unit) => println!("Type your answer as a number in {unit}."),
+            None => println!("Type your answer as a number."),
+        }
+        Question::print_commands(exit_command);
+        loop {
+            let user_input = match Question::read_answer(exit_command, deadline)? {
+                Reply::Answer(user_input) => user_input,
+                Reply::TimeUp => return Some(self.timed_out()),
+                Reply::Skip => return Some(self.skipped()),
+            };
+            let number = match self.read_number(&user_input) {
+                Ok(number) => number,
+                Err(message) => {
+                    println!("{message}");
+                    continue;
+                }
+            };
+            println!();
+            let points = if self.accepts_number(number) {
+                self.max_points()
+            } else {
+                0.0
+            };
+            return Some(UserAnswer::new(self, self.with_unit(number), points));
+        }
+    }
+
+    fn ask_matching(
+        &self,
+        exit_command: &str,
+        deadline: Option<Instant>,
+        layout_seed: Option<u64>,
+    ) -> Option<UserAnswer> {
+        let (left_order, right_order) = self.matching_order(&mut Question::layout_rng(layout_seed));
+
+        let left_width = left_order
+            .iter()
+            .map(|index| self.pairs[*index][0].chars().count())
+            .max()
+            .unwrap_or(0);
+        for (row, (left_index, right_index)) in left_order.iter().zip(&right_order).enumerate() {
+            println!(
+                "[{}] {:<left_width$}   [{}] {}",
+                row + 1,
+                self.pairs[*left_index][0],
+                Question::match_letter(row),
+                self.pairs[*right_index][1]
+            );
+        }
+        println!("Match every number to a letter, like 1-c 2-a.");
+        Question::print_commands(exit_command);
+        loop {
+            let user_input = match Question::read_answer(exit_command, deadline)? {
+                Reply::Answer(user_input) => user_input,
+                Reply::TimeUp => return Some(self.timed_out()),
+                Reply::Skip => return Some(self.skipped()),
+            };
+            let matches = match self.read_matches(&user_input) {
+                Ok(matches) => matches,
+                Err(message) => {
+                    println!("{message}");
+                    continue;
+                }
+            };
+            println!();
+            // pairs as indexes into pairs, item first then the partner it was matched with
+            let mut matched: Vec<(usize, usize)> = matches
+                .iter()
+                .map(|(row, letter_row)| (left_order[*row], right_order[*letter_row]))
+                .collect();
+            matched.sort();
+            let right_pairs = matched
+                .iter()
+                .filter(|(item, partner)| item == partner)
+                .count();
+            let points = right_pairs as f32 / self.pairs.len() as f32 * self.max_points();
+            let answer = matched
+                .iter()
+                .map(|(item, partner)| {
+                    format!("{} = {}", self.pairs[*item][0], self.pairs[*partner][1])
+                })
+                .collect::<Vec<String>>()
+                .join(", ");
+            return Some(UserAnswer::new(self, answer, points));
+        }
+    }
+
+    fn match_letter(row: usize) -> char {
+        Question::MATCH_LETTERS.as_bytes()[row] as char
+    }
+
+    /// turns input like "1-c 2-a" into displayed rows, numbers and letters both counted from 0.
+    /// display order of the items and of the partners.
+    /// like choice answers, pairs stay the answer key and only the display order of each column is shuffled
+    fn matching_order(&self, rng: &mut ChaCha8Rng) -> (Vec<usize>, Vec<usize>) {
+        let mut left_order: Vec<usize> = (0..self.pairs.len()).collect();
+        let mut right_order = left_order.clone();
+        left_order.shuffle(rng);
+        right_order.shuffle(rng);
+        // every partner next to its item would give the answer away
+        while self.pairs.len() > 1 && right_order == left_order {
+            right_order.shuffle(rng);
+        }
+        (left_order, right_order)
+    }
+
+    fn read_matches(&self, user_input: &str) -> Result<Vec<(usize, usize)>, String> {
+        let invalid = || "Invaild input, please enter pairs like 1-c 2-a.".to_string();
+        let mut matches: Vec<(usize, usize)> = Vec::new();
+        for part in user_input
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+        {
+            let (number, letter) = part.split_once('-').ok_or_else(invalid)?;
+            let number: usize = number.parse().map_err(|_| invalid())?;
+            if number == 0 || number > self.pairs.len() {
+                return Err(format!(
+                    "Invaild input, please use numbers between 1 and {}.",
+                    self.pairs.len()
+                ));
+            }
+            let letter = letter.trim().to_lowercase();
+            let letter_row = Question::MATCH_LETTERS[..self.pairs.len()]
+                .find(letter.as_str())
+                .filter(|_| letter.chars().count() == 1)
+                .ok_or_else(|| {
+                    format!(
+                        "Invaild input, please use letters between a and {}.",
+                        Question::match_letter(self.pairs.len() - 1)
+                    )
+                })?;
+            if matches.iter().any(|(row, _)| *row == number - 1) {
+                return Err(format!("{number} is matched more than once."));
+            }
+            if matches
+                .iter()
+                .any(|(_, matched_row)| *matched_row == letter_row)
+            {
+                return Err(format!("{letter} is matched more than once."));
+            }
+            matches.push((number - 1, letter_row));
+        }
+        if matches.is_empty() {
+            return Err(invalid());
+        }
+        Ok(matches)
+    }
+
+    /// parses a typed number, allowing the question unit after it and underscores between digits.
+    fn read_number(&self, user_input: &str) -> Result<f64, String> {
+        let mut number_text = user_input.trim().to_lowercase();
+        if let Some(unit) = &self.unit {
+            if let Some(stripped) = number_text.strip_suffix(&unit.trim().to_lowercase()) {
+                number_text = stripped.trim_end().to_string();
+            }
+        }
+        if number_text.contains(',') {
+            return Err("Please use a '.' for decimals and leave out commas.".to_string());
+        }
+        number_text
+            .replace('_', "")
+            .parse::<f64>()
+            .ok()
+            .filter(|number| number.is_finite())
+            .ok_or("Invaild input, please type a number.".to_string())
+    }
+
+    /// true when the number is inside the range, or close enough to the numeric answer.
+    pub fn accepts_number(&self, number: f64) -> bool {
+        if let Some([lowest, highest]) = self.range {
+            return (lowest..=highest).contains(&number);
+        }
+        let Some(answer) = self.numeric_answer else {
+            return false;
+        };
+        let allowed_difference = match (self.tolerance, self.tolerance_percent) {
+            (Some(tolerance), _) => tolerance,
+            (None, Some(tolerance_percent)) => answer.abs() * tolerance_percent / 100.0,
+            (None, None) => 0.0,
+        };
+        // small slack so answers like 0.1 + 0.2 are not lost to float rounding
+        (number - answer).abs() <= allowed_difference + f64::EPSILON * answer.abs().max(1.0)
+    }
+
+    fn with_unit(&self, number: impl fmt::Display) -> String {
+        match &self.unit {
+            Some(unit) => format!("{number} {unit}"),
+            None => number.to_string(),
+        }
+    }
+
+    /// the accepted answer a free text answer counts as, if any.
+    pub fn matched_answer(&self, user_input: &str) -> Option<&str> {
+        text_match::find_match(user_input, &self.accepted_answers, self.max_typos)
+    }
+
+    /// the accepted answer a typed blank counts as, if any.
+    fn matched_blank(&self, blank: usize, user_input: &str) -> Option<&str> {
+        text_match::find_match(user_input, &self.blanks[blank], self.max_typos)
+    }
+
+    /// turns user input into the displayed answer numbers it names, without duplicates.
+    fn read_picks(&self, user_input: &str) -> Result<Vec<usize>, String> {
+        let mut picked_numbers: Vec<usize> = Vec::new();
+        for part in user_input
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+        {
+            let number: usize = part.parse().map_err(|_| {
+                "Invaild input, please enter your guess by typing its corresponding number"
+                    .to_string()
+            })?;
+            if !self.is_answer_number(number) {
+                return Err(format!(
+                    "Invaild input, please enter a number between 1 and {}.",
+                    self.answers.len()
+                ));
+            }
+            if picked_numbers.contains(&number) {
+                if self.kind == QuestionKind::Ordering {
+                    return Err(format!("{number} is in your order more than once."));
+                }
+                continue;
+            }
+            picked_numbers.push(number);
+        }
+
+        match (self.kind, picked_numbers.len()) {
+            (_, 0) => Err(
+                "Invaild input, please enter your guess by typing its corresponding number"
+                    .to_string(),
+            ),
+            (QuestionKind::MultiSelect, _) => Ok(picked_numbers),
+            (QuestionKind::Ordering, count) if count == self.answers.len() => Ok(picked_numbers),
+            (QuestionKind::Ordering, _) => Err(format!(
+                "Please put all {} items in order.",
+                self.answers.len()
+            )),
+            (_, 1) => Ok(picked_numbers),
+            (_, _) => Err("Only one answer is correct, please enter a single number.".to_string()),
+        }
+    }
+
+    /// scores the picked answers of a choice question, given as indexes into answers.
+    fn answered(&self, picked: &[usize]) -> UserAnswer {
+        let points = match self.kind {
+            QuestionKind::Ordering => self.order_score(picked),
+            QuestionKind::MultiSelect => {
+                let right_picks = picked
+                    .iter()
+                    .filter(|index| self.correct_answers.contains(&(*index + 1)))
+                    .count();
+                let wrong_picks = picked.len() - right_picks;
+                match self.scoring {
+                    // kendall tau is rejected on validation, it only ranks orders
+                    Scoring::AllOrNothing | Scoring::KendallTau => {
+                        if wrong_picks == 0 && right_picks == self.correct_answers.len() {
+                            1.0
+                        } else {
+                            0.0
+                        }
+                    }
+                    Scoring::Partial => {
+                        (right_picks as f32 - wrong_picks as f32).max(0.0)
+                            / self.correct_answers.len() as f32
+                    }
+                }
+            }
+            _ => {
+                if Some(picked[0] + 1) == self.correct_answer {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        };
+
+        let answer = self.join_answers(picked.iter().copied());
+        let mut user_answer = UserAnswer::new(self, answer, points * self.max_points());
+        user_answer.picked = picked.to_vec();
+        user_answer
+    }
+
+    /// share of the point for putting the answers in the picked order.
+    fn order_score(&self, picked: &[usize]) -> f32 {
+        let item_count = picked.len();
+        match self.scoring {
+            Scoring::AllOrNothing => {
+                if picked
+                    .iter()
+                    .enumerate()
+                    .all(|(position, index)| position == *index)
+                {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Scoring::Partial => {
+                let in_place = picked
+                    .iter()
+                    .enumerate()
+                    .filter(|(position, index)| position == *index)
+                    .count();
+                in_place as f32 / item_count as f32
+            }
+            Scoring::KendallTau => {
+                let pairs = item_count * (item_count - 1) / 2;
+                let swapped_pairs = (0..item_count)
+                    .flat_map(|first| (first + 1..item_count).map(move |second| (first, second)))
+                    .filter(|(first, second)| picked[*first] > picked[*second])
+                    .count();
+                1.0 - swapped_pairs as f32 / pairs as f32
+            }
+        }
+    }
+
+    /// texts of the answers at the given indexes, in order.
+    fn join_answers(&self, indexes: impl Iterator<Item = usize>) -> String {
+        let separator = match self.kind {
+            QuestionKind::Ordering => " -> ",
+            _ => ", ",
+        };
+        indexes
+            .map(|index| self.answers[index].text())
+            .collect::<Vec<&str>>()
+            .join(separator)
+    }
+
+    /// text of the correct answer, every correct answer for multi select questions.
+    pub fn correct_answer_text(&self) -> String {
+        match self.kind {
+            QuestionKind::FreeText => self.accepted_answers.first().cloned().unwrap_or_default(),
+            QuestionKind::Numeric => match (self.range, self.numeric_answer) {
+                (Some([lowest, highest]), _) => {
+                    format!("{} to {}", self.with_unit(lowest), self.with_unit(highest))
+                }
+                (None, Some(answer)) => match (self.tolerance, self.tolerance_percent) {
+                    (Some(tolerance), _) if tolerance > 0.0 => {
+                        format!("{} (± {tolerance})", self.with_unit(answer))
+                    }
+                    (None, Some(tolerance_percent)) if tolerance_percent > 0.0 => {
+                        format!("{} (± {tolerance_percent}%)", self.with_unit(answer))
+                    }
+                    _ => self.with_unit(answer),
+                },
+                (None, None) => String::new(),
+            },
+            QuestionKind::MultipleChoice => self
+                .correct_answer
+                .map(|number| self.answers[number - 1].text().to_string())
+                .unwrap_or_default(),
+            QuestionKind::MultiSelect => {
+                self.join_answers(self.correct_answers.iter().map(|number| number - 1))
+            }
+            QuestionKind::Ordering => self.join_answers(0..self.answers.len()),
+            QuestionKind::Matching => self
+                .pairs
+                .iter()
+                .map(|[item, partner]| format!("{item} = {partner}"))
+                .collect::<Vec<String>>()
+                .join(", "),
+            QuestionKind::Cloze => {
+                self.fill_blanks(|blank| self.blanks[blank].first().cloned().unwrap_or_default())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn question(toml: &str) -> Question {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn multi_select(scoring: &str) -> Question {
+        question(&format!(
+            r#"
+            question = "pick the vowels"
+            kind = "multi_select"
+            answers = ["a", "b", "e", "d"]
+            correct_answers = [1, 3]
+            scoring = "{scoring}"
+            "#
+        ))
+    }
+
+    #[test]
+    fn seeded_layouts_stay_the_same() {
+        // saves rebuild the answer order from the seed, a change here breaks resuming old saves
+        let question = question(
+            r#"
+            question = "pick one"
+            answers = ["a", "b", "c", "d", "e", { text = "none of these", pinned = "last" }]
+            correct_answer = 1
+            "#,
+        );
+        let layout = |seed| question.display_order(&mut Question::layout_rng(Some(seed)));
+        assert_eq!(layout(1), vec![4, 1, 3, 0, 2, 5]);
+        assert_eq!(layout(2), vec![3, 0, 1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn default_kind_and_scoring_are_left_out_when_saving() {
+        let plain = toml::to_string(&question(
+            r#"
+            question = "pick one"
+            answers = ["a", "b"]
+            correct_answer = 1
+            "#,
+        ))
+        .unwrap();
+        assert!(!plain.contains("kind"), "{plain}");
+        assert!(!plain.contains("scoring"), "{plain}");
+
+        let partial = toml::to_string(&multi_select("partial")).unwrap();
+        assert!(partial.contains("kind = \"multi_select\""), "{partial}");
+        assert!(partial.contains("scoring = \"partial\""), "{partial}");
+    }
+
+    #[test]
+    fn partial_scoring_takes_a_share_away_for_wrong_picks() {
+        let question = multi_select("partial");
+        assert_eq!(question.answered(&[0, 2]).points, 1.0);
+        assert_eq!(question.answered(&[0]).points, 0.5);
+        assert_eq!(question.answered(&[0, 1]).points, 0.0);
+        // never below zero
+        assert_eq!(question.answered(&[1, 3]).points, 0.0);
+        assert_eq!(question.answered(&[2, 0]).answer, "e, a");
+    }
+
+    #[test]
+    fn all_or_nothing_needs_every_right_pick_and_no_wrong_one() {
+        let question = multi_select("all_or_nothing");
+        assert!(question.answered(&[2, 0]).is_correct());
+        assert_eq!(question.answered(&[0]).points, 0.0);
+        assert_eq!(question.answered(&[0, 1, 2]).points, 0.0);
+    }
+
+    #[test]
+    fn picks_are_read_once_each() {
+        let multi = multi_select("partial");
+        assert_eq!(multi.read_picks("1, 3 1"), Ok(vec![1, 3]));
+        assert!(multi.read_picks("5").is_err());
+        assert!(multi.read_picks("one").is_err());
+        assert!(multi.read_picks(" ").is_err());
+
+        let single = question(
+            r#"
+            question = "pick a"
+            answers = ["a", "b"]
+            correct_answer = 1
+            "#,
+        );
+        assert_eq!(single.read_picks("2"), Ok(vec![2]));
+        assert!(single.read_picks("1 2").is_err());
+    }
+
+    #[test]
+    fn reports_broken_correct_answers() {
+        let question = question(
+            r#"
+            question = "pick"
+            kind = "multi_select"
+            answers = ["a", "b"]
+            correct_answers = [2, 3, 2]
+            correct_answer = 1
+            "#,
+        );
+        assert_eq!(
+            question.problems(),
+            vec![
+                "correct_answers has 3 but must be between 1 and 2",
+                "correct_answers lists 2 more than once",
+                "correct_answer only works for multiple_choice questions",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_fields_of_another_kind() {
+        let question = question(
+            r#"
+            question = "how far?"
+            kind = "numeric"
+            numeric_answer = 3.0
+            pairs = [["a", "b"], ["c", "d"]]
+            scoring = "partial"
+            max_typos = 1
+            "#,
+        );
+        assert_eq!(
+            question.problems(),
+            vec![
+                "scoring only works for multi_select and ordering questions",
+                "max_typos only works for free_text and cloze questions",
+                "pairs only works for matching questions",
+            ]
+        );
+        let mut choice = multi_select("partial");
+        choice.kind = QuestionKind::MultipleChoice;
+        choice.correct_answer = Some(1);
+        assert_eq!(
+            choice.problems(),
+            vec![
+                "correct_answers only works for multi_select questions",
+                "scoring only works for multi_select and ordering questions",
+            ]
+        );
+    }
+
+    fn numeric(fields: &str) -> Question {
+        question(&format!(
+            r#"
+            question = "how far?"
+            kind = "numeric"
+            {fields}
+            "#
+        ))
+    }
+
+    #[test]
+    fn reads_numbers_with_whitespace_underscores_and_the_unit() {
+        let question = numeric("numeric_answer = 1000\nunit = \"km\"");
+        assert_eq!(question.read_number("  42 "), Ok(42.0));
+        assert_eq!(question.read_number("1_000"), Ok(1000.0));
+        assert_eq!(question.read_number("-2.5e3"), Ok(-2500.0));
+        assert_eq!(question.read_number("12 km"), Ok(12.0));
+        assert_eq!(question.read_number("12KM"), Ok(12.0));
+        assert!(question.read_number("12 m").is_err());
+        assert!(question.read_number("km").is_err());
+    }
+
+    #[test]
+    fn rejects_comma_decimals_and_numbers_that_are_not_finite() {
+        let question = numeric("numeric_answer = 3.5");
+        assert_eq!(
+            question.read_number("3,5"),
+            Err("Please use a '.' for decimals and leave out commas.".to_string())
+        );
+        for input in ["NaN", "inf", "-infinity", "1e400", ""] {
+            assert_eq!(
+                question.read_number(input),
+                Err("Invaild input, please type a number.".to_string()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn accepts_numbers_within_the_tolerance() {
+        let exact = numeric("numeric_answer = 0.3");
+        assert!(exact.accepts_number(0.1 + 0.2));
+        assert!(!exact.accepts_number(0.31));
+
+        let absolute = numeric("numeric_answer = 100\ntolerance = 5");
+        assert!(absolute.accepts_number(95.0));
+        assert!(absolute.accepts_number(105.0));
+        assert!(!absolute.accepts_number(105.01));
+
+        let percent = numeric("numeric_answer = -50\ntolerance_percent = 10");
+        assert!(percent.accepts_number(-55.0));
+        assert!(percent.accepts_number(-45.0));
+        assert!(!percent.accepts_number(-44.9));
+    }
+
+    #[test]
+    fn ranges_include_both_ends() {
+        let question = numeric("range = [1.5, 3]");
+        assert!(question.accepts_number(1.5));
+        assert!(question.accepts_number(3.0));
+        assert!(!question.accepts_number(1.49));
+        assert!(!question.accepts_number(3.0001));
+        assert_eq!(question.correct_answer_text(), "1.5 to 3");
+    }
+
+    #[test]
+    fn reports_conflicting_numeric_answers() {
+        assert_eq!(
+            numeric("").problems(),
+            vec!["numeric_answer or range is missing"]
+        );
+        assert_eq!(
+            numeric("range = [3, 1]\ntolerance = -1").problems(),
+            vec![
+                "range starts at 3 which is above its end 1",
+                "a range can not have a tolerance",
+                "tolerance can not be negative",
+            ]
+        );
+    }
+
+    fn ordering(scoring: &str) -> Question {
+        question(&format!(
+            r#"
+            question = "order these"
+            kind = "ordering"
+            answers = ["a", "b", "c", "d"]
+            scoring = "{scoring}"
+            "#
+        ))
+    }
+
+    #[test]
+    fn kendall_tau_gives_a_share_per_pair_in_order() {
+        let question = ordering("kendall_tau");
+        assert_eq!(question.order_score(&[0, 1, 2, 3]), 1.0);
+        assert_eq!(question.order_score(&[3, 2, 1, 0]), 0.0);
+        // one of six pairs swapped
+        assert_eq!(question.order_score(&[1, 0, 2, 3]), 5.0 / 6.0);
+        // b, c and d each come before a, three of six pairs swapped
+        assert_eq!(question.order_score(&[1, 2, 3, 0]), 0.5);
+    }
+
+    #[test]
+    fn positions_give_a_share_per_item_in_place() {
+        let question = ordering("positions");
+        assert_eq!(question.order_score(&[1, 2, 3, 0]), 0.0);
+        assert_eq!(question.order_score(&[0, 1, 3, 2]), 0.5);
+    }
+
+    #[test]
+    fn exact_needs_the_whole_order() {
+        let question = ordering("exact");
+        assert_eq!(question.order_score(&[0, 1, 2, 3]), 1.0);
+        assert_eq!(question.order_score(&[0, 1, 3, 2]), 0.0);
+    }
+
+    #[test]
+    fn orders_list_every_item_once() {
+        let question = ordering("exact");
+        assert_eq!(question.read_picks("4 1 3 2"), Ok(vec![4, 1, 3, 2]));
+        assert_eq!(
+            question.read_picks("4 1 4 2"),
+            Err("4 is in your order more than once.".to_string())
+        );
+        assert_eq!(
+            question.read_picks("4 1 3"),
+            Err("Please put all 4 items in order.".to_string())
+        );
+        let user_answer = question.answered(&[1, 2, 3, 0]);
+        assert_eq!(user_answer.answer, "b -> c -> d -> a");
+        assert_eq!(question.correct_answer_text(), "a -> b -> c -> d");
+    }
+
+    fn matching() -> Question {
+        question(
+            r#"
+            question = "match the traits"
+            kind = "matching"
+            pairs = [["Copy", "bitwise copy"], ["Clone", "explicit copy"], ["Drop", "cleanup"]]
+            "#,
+        )
+    }
+
+    #[test]
+    fn reads_matches_as_rows_and_letter_rows() {
+        let question = matching();
+        assert_eq!(
+            question.read_matches("1-c 2-a,3-B"),
+            Ok(vec![(0, 2), (1, 0), (2, 1)])
+        );
+        // leaving pairs out is allowed, they score nothing
+        assert_eq!(question.read_matches(" 2-b "), Ok(vec![(1, 1)]));
+    }
+
+    #[test]
+    fn partners_are_never_shown_next_to_their_items() {
+        let mut question = matching();
+        for pair_count in [3, 2] {
+            question.pairs.truncate(pair_count);
+            for seed in 0..100 {
+                let (left_order, right_order) =
+                    question.matching_order(&mut Question::layout_rng(Some(seed)));
+                assert_ne!(left_order, right_order, "seed {seed}");
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_matches() {
+        let question = matching();
+        let invalid = "Invaild input, please enter pairs like 1-c 2-a.".to_string();
+        for input in ["", "1c", "one-a", "1 - a", "-a", "a-1"] {
+            assert_eq!(
+                question.read_matches(input),
+                Err(invalid.clone()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_matches_out_of_range() {
+        let question = matching();
+        let numbers = "Invaild input, please use numbers between 1 and 3.".to_string();
+        let letters = "Invaild input, please use letters between a and c.".to_string();
+        assert_eq!(question.read_matches("0-a"), Err(numbers.clone()));
+        assert_eq!(question.read_matches("4-a"), Err(numbers));
+        assert_eq!(question.read_matches("1-d"), Err(letters.clone()));
+        assert_eq!(question.read_matches("1-"), Err(letters.clone()));
+        assert_eq!(question.read_matches("1-ab"), Err(letters));
+    }
+
+    #[test]
+    fn rejects_matching_a_row_or_letter_twice() {
+        let question = matching();
+        assert_eq!(
+            question.read_matches("1-a 1-b"),
+            Err("1 is matched more than once.".to_string())
+        );
+        assert_eq!(
+            question.read_matches("1-a 2-A"),
+            Err("a is matched more than once.".to_string())
+        );
+    }
+
+    #[test]
+    fn reports_broken_pairs() {
+        let question = question(
+            r#"
+            question = "match"
+            kind = "matching"
+            answers = ["a", "b"]
+            pairs = [["a", " "], ["a", "b"]]
+            "#,
+        );
+        assert_eq!(
+            question.problems(),
+            vec![
+                "pair 1 has an empty item",
+                "'a' is listed more than once",
+                "matching questions use pairs, not answers",
+            ]
+        );
+    }
+
+    fn cloze(text: &str, blanks: &str) -> Question {
+        question(&format!(
+            r#"
+            question = "{text}"
+            kind = "cloze"
+            blanks = {blanks}
+            "#
+        ))
+    }
+
+    #[test]
+    fn finds_blank_markers_in_order() {
+        let question = cloze("{{2}} owns {{1}}, {{x}} and {{3", "[]");
+        assert_eq!(question.blank_markers(), vec![2, 1]);
+        assert!(cloze("no blanks {}", "[]").blank_markers().is_empty());
+        // only markers fill_blanks replaces count
+        let question = cloze("{{ 1 }}, {{02}}, {{+3}} and {{4}}", "[]");
+        assert_eq!(question.blank_markers(), vec![4]);
+    }
+
+    #[test]
+    fn reports_markers_that_would_not_be_filled() {
+        let question = cloze("{{ 1 }} owns {{02}}", r#"[["a"], ["b"]]"#);
+        assert_eq!(
+            question.problems(),
+            vec![
+                "blank marker {{ 1 }} must be written as {{1}}",
+                "blank marker {{02}} must be written as {{2}}",
+                "question text has no {{1}} blank",
+                "question text has no {{2}} blank",
+            ]
+        );
+    }
+
+    #[test]
+    fn fills_every_blank_with_its_text() {
+        let question = cloze(
+            "a value has {{1}} owner, dropped at the end of {{2}}",
+            r#"[["one", "1"], ["scope"]]"#,
+        );
+        assert_eq!(
+            question.prompt_text(),
+            "a value has ____(1) owner, dropped at the end of ____(2)"
+        );
+        assert_eq!(
+            question.correct_answer_text(),
+            "a value has one owner, dropped at the end of scope"
+        );
+        assert_eq!(question.matched_blank(0, " 1 "), Some("1"));
+        assert_eq!(question.matched_blank(1, "Scope"), Some("scope"));
+        assert_eq!(question.matched_blank(1, "block"), None);
+        // a marker is replaced wherever it is, even next to other text
+        let question = cloze("{{1}}{{2}}!", r#"[["a"], ["b"]]"#);
+        assert_eq!(question.fill_blanks(|blank| blank.to_string()), "01!");
+    }
+
+    #[test]
+    fn reports_blanks_that_do_not_fit_the_text() {
+        let question = cloze("{{1}} and {{1}} and {{3}}", r#"[["a"], [" "]]"#);
+        assert_eq!(
+            question.problems(),
+            vec![
+                "question text has {{1}} more than once",
+                "question text has no {{2}} blank",
+                "question text has {{3}} but blanks only has 2 entries",
+                "blank 2 has no accepted answer",
+            ]
+        );
+        assert_eq!(
+            cloze("{{1}}", "[]").problems(),
+            vec![
+                "blanks is missing",
+                "question text has {{1}} but blanks only has 0 entries"
+            ]
+        );
+    }
+
+    #[test]
+    fn shares_are_scaled_by_points() {
+        let mut question = ordering("kendall_tau");
+        question.points = 2.0;
+        assert_eq!(question.answered(&[1, 2, 3, 0]).points, 1.0);
+        assert!(question.answered(&[0, 1, 2, 3]).is_correct());
+        let mut multi = multi_select("partial");
+        multi.points = 3.0;
+        assert_eq!(multi.answered(&[0]).points, 1.5);
+        let skipped = multi.skipped();
+        assert!(skipped.skipped);
+        assert_eq!(skipped.points, 0.0);
+    }
+
+    #[test]
+    fn points_must_be_above_zero() {
+        let mut question = multi_select("partial");
+        question.points = 0.0;
+        assert_eq!(question.problems(), vec!["points is 0 but must be above 0"]);
+    }
+
+    #[test]
+    fn legacy_answers_are_scored_by_their_text() {
+        let question: Question = toml::from_str(
+            r#"
+            question = "pick a"
+            answers = ["a", "b"]
+            correct_answer = 1
+            "#,
+        )
+        .unwrap();
+        assert!(UserAnswer::from_legacy(question.clone(), "a".to_string()).is_correct());
+        assert_eq!(
+            UserAnswer::from_legacy(question.clone(), "b".to_string()).points,
+            0.0
+        );
+        assert_eq!(
+            UserAnswer::from_legacy(question, "gone".to_string()).points,
+            0.0
+        );
+    }
+}
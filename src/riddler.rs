@@ -1,99 +1,604 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use chrono::Local;
 use colored::Colorize;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
-use serde::{Deserialize, Serialize};
+use rand::{thread_rng, Rng, SeedableRng};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
 use std::io::Write;
-use std::{fmt, path::Path};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use std::{fs, fs::File};
 
 use include_dir::{include_dir, Dir};
 
-// rebuild if you add any new quizzes
+// rebuild if you add any new quizzes here, quizes in the user quiz dir are read at runtime
 static PROJECT_DIR: Dir = include_dir!("src/quizzes/");
 
+use crate::branching;
+use crate::config::Config;
+use crate::grading::GradingScale;
+use crate::history::History;
+use crate::markdown;
+use crate::message_packs::MessagePack;
+use crate::pool::Pool;
+use crate::question::{Question, UserAnswer};
+use crate::template;
 use crate::tools;
 
+/// user input that stops a single examination and writes a save file.
+pub const SAVE_AND_QUIT: &str = "save and quit";
+/// largest seed a save file can hold, toml integers are signed
+pub const MAX_SEED: u64 = i64::MAX as u64;
+/// user input that leaves study mode.
+const STOP_STUDYING: &str = "stop studying";
+
+/// Where a stored quiz was loaded from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum QuizSource {
+    #[default]
+    BuiltIn,
+    File(PathBuf),
+}
+
+impl fmt::Display for QuizSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QuizSource::BuiltIn => write!(f, "built-in"),
+            QuizSource::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct ReadyQuiz {
     pub quiz_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// rough time to finish the quiz, shown in the catalog
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_minutes: Option<u32>,
+    #[serde(default)]
+    pub questions: Vec<Question>,
+    /// parts of the quiz, asked after the questions outside of any section
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sections: Vec<Section>,
+    /// seconds for the whole quiz, unanswered questions are wrong once it runs out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_limit: Option<u64>,
+    /// share of a question's points taken away for a wrong answer, skipped questions are not penalized
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub negative_marking: Option<f32>,
+    /// how the result is graded, the config file scale when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grading: Option<GradingScale>,
+    /// messages per letter grade, the config message pack fills in letters left out
+    #[serde(default, skip_serializing_if = "MessagePack::is_empty")]
+    pub messages: MessagePack,
+    /// draws a random subset of the questions for every attempt instead of asking them all
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool: Option<Pool>,
+    /// asks the questions in a random order instead of the file order
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub shuffle_questions: bool,
+    #[serde(skip)]
+    pub source: QuizSource,
+}
+
+/// A part of a quiz with its own intro, questions and time limit, scored on its own in the result.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Section {
+    pub name: String,
+    /// shown before the first question of the section
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intro: Option<String>,
+    /// seconds for the questions of this section
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_limit: Option<u64>,
+    /// moved to the quiz questions on loading, each marked with the section name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub questions: Vec<Question>,
 }
 
+/// Narrows the catalog to quizes with at least one question of a tag or difficulty.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuizFilter {
+    Tag(String),
+    Difficulty(String),
+}
+
+impl Section {
+    /// shows the section name and intro until the user is ready to start.
+    fn print_intro(&self) {
+        tools::clear_terminal();
+        println!("{}\n", self.name.bold());
+        if let Some(intro) = &self.intro {
+            println!("{intro}\n");
+        }
+        println!("Press enter to start this section.");
+        tools::read_input();
+    }
+}
+
+impl QuizFilter {
+    /// reads "tag <tag>" or "difficulty <level>", none for any other input.
+    pub fn parse(user_input: &str) -> Option<QuizFilter> {
+        let (kind, value) = user_input.trim().split_once(' ')?;
+        let value = value.trim().to_lowercase();
+        match kind {
+            "tag" => Some(QuizFilter::Tag(value)),
+            "difficulty" => Some(QuizFilter::Difficulty(value)),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, quiz: &ReadyQuiz) -> bool {
+        quiz.questions.iter().any(|question| match self {
+            QuizFilter::Tag(tag) => question
+                .tags
+                .iter()
+                .any(|question_tag| question_tag.trim().to_lowercase() == *tag),
+            QuizFilter::Difficulty(difficulty) => {
+                question
+                    .difficulty
+                    .as_ref()
+                    .is_some_and(|question_difficulty| {
+                        question_difficulty.trim().to_lowercase() == *difficulty
+                    })
+            }
+        })
+    }
+}
+
+impl fmt::Display for QuizFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QuizFilter::Tag(tag) => write!(f, "tag '{tag}'"),
+            QuizFilter::Difficulty(difficulty) => write!(f, "difficulty '{difficulty}'"),
+        }
+    }
+}
+
+/// Directory users can drop their own quiz files in.
+pub fn user_quiz_dir() -> PathBuf {
+    tools::data_dir().join("quizzes")
+}
+
 impl ReadyQuiz {
-    fn ready_quiz_to_quiz(self) -> Quiz {
+    /// Reads and parses a single quiz file.
+    pub fn from_file(path: &Path) -> Result<ReadyQuiz> {
+        let quiz_contents = fs::read_to_string(path)?;
+        let mut quiz = ReadyQuiz::parse(&quiz_contents)?;
+        quiz.source = QuizSource::File(path.to_path_buf());
+        Ok(quiz)
+    }
+
+    /// Parses and validates quiz file contents, section questions join the quiz questions.
+    pub fn parse(quiz_contents: &str) -> Result<ReadyQuiz> {
+        let mut quiz: ReadyQuiz = toml::from_str(quiz_contents)?;
+        for section in &mut quiz.sections {
+            for mut question in section.questions.drain(..) {
+                question.section = Some(section.name.clone());
+                quiz.questions.push(question);
+            }
+        }
+        quiz.validate()?;
+        Ok(quiz)
+    }
+
+    /// Checks every question, reporting all mistakes at once with their question number.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems: Vec<String> = Vec::new();
+        if self.questions.is_empty() {
+            problems.push("quiz has no questions".to_string());
+        }
+        if self.time_limit == Some(0) {
+            problems.push("time_limit must be at least 1 second".to_string());
+        }
+        if let Some(negative_marking) = self.negative_marking {
+            if !(0.0..=1.0).contains(&negative_marking) {
+                problems.push(format!(
+                    "negative_marking is {negative_marking} but must be between 0 and 1"
+                ));
+            }
+        }
+        if let Some(pool) = &self.pool {
+            problems.extend(pool.problems(self.questions.len()));
+        }
+        if let Some(grading) = &self.grading {
+            problems.extend(grading.problems());
+        }
+        for (index, section) in self.sections.iter().enumerate() {
+            if section.name.trim().is_empty() {
+                problems.push(format!("section {} has no name", index + 1));
+            }
+            if self.sections[..index]
+                .iter()
+                .any(|earlier| earlier.name == section.name)
+            {
+                problems.push(format!(
+                    "section '{}' is listed more than once",
+                    section.name
+                ));
+            }
+            if section.time_limit == Some(0) {
+                problems.push(format!(
+                    "section '{}': time_limit must be at least 1 second",
+                    section.name
+                ));
+            }
+            if !self
+                .questions
+                .iter()
+                .any(|question| question.section.as_ref() == Some(&section.name))
+            {
+                problems.push(format!("section '{}' has no questions", section.name));
+            }
+        }
+        for (index, question) in self.questions.iter().enumerate() {
+            for problem in question.problems() {
+                problems.push(format!("question {}: {problem}", index + 1));
+            }
+            if let Some(section) = &question.section {
+                if !self.sections.iter().any(|listed| listed.name == *section) {
+                    problems.push(format!(
+                        "question {}: section '{section}' is not listed in sections",
+                        index + 1
+                    ));
+                }
+            }
+        }
+        problems.extend(branching::problems(&self.questions));
+        // links lead by position in the file, drawing or shuffling would break them
+        if branching::is_branching(&self.questions) {
+            if self.pool.is_some() {
+                problems.push(
+                    "questions with next_on_correct or next_on_wrong can not be drawn from a pool"
+                        .to_string(),
+                );
+            }
+            if self.shuffle_questions {
+                problems.push(
+                    "questions with next_on_correct or next_on_wrong can not be shuffled"
+                        .to_string(),
+                );
+            }
+        }
+        if !problems.is_empty() {
+            bail!(
+                "'{}' is invalid:\n  {}",
+                self.quiz_name,
+                problems.join("\n  ")
+            );
+        }
+        Ok(())
+    }
+
+    /// prints the indented metadata lines of the quiz, nothing for a quiz without metadata.
+    fn print_metadata(&self) {
+        if let Some(description) = &self.description {
+            println!("  {description}");
+        }
+        let credits: Vec<String> = [
+            self.author.as_ref().map(|author| format!("by {author}")),
+            self.version
+                .as_ref()
+                .map(|version| format!("version {version}")),
+            self.license
+                .as_ref()
+                .map(|license| format!("license {license}")),
+            self.estimated_minutes
+                .map(|minutes| format!("about {minutes} minutes")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if !credits.is_empty() {
+            println!("  {}", credits.join(", "));
+        }
+        let tags = self.collect_labels(|question| question.tags.clone());
+        if !tags.is_empty() {
+            println!("  tags: {}", tags.join(", "));
+        }
+        let difficulties =
+            self.collect_labels(|question| question.difficulty.iter().cloned().collect());
+        if !difficulties.is_empty() {
+            println!("  difficulty: {}", difficulties.join(", "));
+        }
+    }
+
+    /// every distinct label of the questions, lowercased and in order of first use.
+    fn collect_labels(&self, labels_of: impl Fn(&Question) -> Vec<String>) -> Vec<String> {
+        let mut labels: Vec<String> = Vec::new();
+        for label in self.questions.iter().flat_map(labels_of) {
+            let label = label.trim().to_lowercase();
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+        labels
+    }
+
+    /// a fresh attempt at the quiz, with the questions drawn from the pool if it has one.
+    /// the seed decides the drawn questions, their order and the order of their answers.
+    fn ready_quiz_to_quiz(self, seed: u64) -> Quiz {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let questions = match &self.pool {
+            Some(pool) => pool.draw(self.questions, &mut rng),
+            None => self.questions,
+        };
+        // templates get their numbers from the seed too, so a seed repeats the same drills
+        let mut questions: Vec<Question> = questions
+            .iter()
+            .filter_map(|question| match template::instantiate(question, &mut rng) {
+                Ok(question) => Some(question),
+                Err(e) => {
+                    println!("Could not generate '{}': {e}", question.question);
+                    None
+                }
+            })
+            .collect();
+        if self.shuffle_questions {
+            questions.shuffle(&mut rng);
+        }
+        // questions are only shuffled within their section, sections keep their order
+        questions.sort_by_key(|question| {
+            question.section.as_ref().map(|name| {
+                self.sections
+                    .iter()
+                    .position(|section| section.name == *name)
+            })
+        });
         Quiz {
             quiz_name: self.quiz_name,
-            questions: self.questions,
-            user_answers: Vec::<(Question, String)>::new(),
-            score: 0,
+            questions,
+            user_answers: Vec::<UserAnswer>::new(),
+            score: 0.0,
+            show_explanations: false,
+            time_limit: self.time_limit,
+            negative_marking: self.negative_marking,
+            grading: self.grading,
+            messages: self.messages,
+            seed: Some(seed),
+            sections: self.sections,
+            path: Vec::new(),
+            seconds_used: 0.0,
         }
     }
 
-    pub fn load_included_quizes() -> Result<Vec<Quiz>> {
-        let mut cached_quizes: Vec<Quiz> = Vec::new();
+    pub fn load_included_quizes() -> Result<Vec<ReadyQuiz>> {
+        let mut cached_quizes: Vec<ReadyQuiz> = Vec::new();
         for entry in PROJECT_DIR.find("*.toml")? {
             let quiz_contents = PROJECT_DIR
                 .get_file(entry.path())
                 .expect("could not find file")
                 .contents_utf8()
                 .expect("could not retreive contents");
-            let deserial_attempt: ReadyQuiz = toml::from_str(&quiz_contents)?;
+            let deserial_attempt = ReadyQuiz::parse(quiz_contents)?;
             // dbg!(&deserial_attempt);
-            cached_quizes.push(deserial_attempt.ready_quiz_to_quiz());
+            cached_quizes.push(deserial_attempt);
         }
-        return Ok(cached_quizes);
+        Ok(cached_quizes)
+    }
+
+    /// Loads every quiz file directly inside dir, sorted by file name.
+    /// Files that fail to load are returned as messages instead of failing the whole dir.
+    pub fn load_dir_quizes(dir: &Path) -> Result<(Vec<ReadyQuiz>, Vec<String>)> {
+        let mut quiz_files: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        quiz_files.sort();
+
+        let mut quizes: Vec<ReadyQuiz> = Vec::new();
+        let mut problems: Vec<String> = Vec::new();
+        for quiz_file in quiz_files {
+            match ReadyQuiz::from_file(&quiz_file) {
+                Ok(quiz) => quizes.push(quiz),
+                Err(e) => problems.push(format!("skipped {}: {e}", quiz_file.display())),
+            }
+        }
+        Ok((quizes, problems))
     }
 }
 
+/// Every quiz available to play, built-in ones first, followed by the quiz dirs in order.
 #[derive(Clone)]
-pub struct QuizList(pub Vec<Quiz>);
+pub struct QuizList {
+    pub quizes: Vec<ReadyQuiz>,
+    /// quiz files or dirs that could not be read
+    pub problems: Vec<String>,
+}
 
 impl QuizList {
     /// creates a quizes struct for loading all quizes to display to user
-    pub fn load_stored_quizes() -> Result<QuizList> {
-        let cached_quizes = ReadyQuiz::load_included_quizes()?;
-        let quizes = QuizList(cached_quizes);
-        Ok(quizes)
+    /// a quiz with the same name as an earlier one replaces it, so user quizes can override built-in ones.
+    pub fn load_stored_quizes(config: &Config) -> Result<QuizList> {
+        let mut quiz_list = QuizList {
+            quizes: ReadyQuiz::load_included_quizes()?,
+            problems: Vec::new(),
+        };
+
+        for (dir, required) in config.quiz_dirs() {
+            if !dir.is_dir() {
+                if required {
+                    quiz_list
+                        .problems
+                        .push(format!("quiz dir {} does not exist", dir.display()));
+                }
+                continue;
+            }
+            let (dir_quizes, problems) = match ReadyQuiz::load_dir_quizes(&dir) {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    quiz_list
+                        .problems
+                        .push(format!("could not read quiz dir {}: {e}", dir.display()));
+                    continue;
+                }
+            };
+            quiz_list.problems.extend(problems);
+            for quiz in dir_quizes {
+                quiz_list.add(quiz);
+            }
+        }
+        Ok(quiz_list)
+    }
+
+    fn add(&mut self, quiz: ReadyQuiz) {
+        let name = quiz.quiz_name.trim().to_lowercase();
+        self.quizes
+            .retain(|stored| stored.quiz_name.trim().to_lowercase() != name);
+        self.quizes.push(quiz);
+    }
+
+    /// Finds a quiz by its lowercased name.
+    pub fn find(&self, input_quiz_name: &str) -> Option<&ReadyQuiz> {
+        self.quizes
+            .iter()
+            .find(|quiz| input_quiz_name == quiz.quiz_name.trim().to_lowercase())
     }
 
     /// Search quizes struct for user input quiz to prepare for test taking.
-    pub fn ready_quiz(self, input_quiz_name: String) -> Option<Quiz> {
-        let mut quiz: Option<Quiz> = None;
+    /// a random seed is picked when none is given, so the attempt can still be resumed as it was.
+    pub fn ready_quiz(self, input_quiz_name: String, seed: Option<u64>) -> Option<Quiz> {
+        let seed = seed.unwrap_or_else(|| thread_rng().gen_range(0..=MAX_SEED));
+        self.find(&input_quiz_name)
+            .map(|quiz| quiz.clone().ready_quiz_to_quiz(seed))
+    }
 
-        for single_quiz in self.0 {
-            if input_quiz_name == single_quiz.quiz_name.trim().to_lowercase() {
-                quiz = Some(single_quiz.clone());
+    /// prints every quiz the filter lets through with its metadata, and the problems found on loading.
+    pub fn print_catalog(&self, filter: Option<&QuizFilter>) {
+        let mut shown = 0;
+        for quiz in &self.quizes {
+            if filter.is_some_and(|filter| !filter.matches(quiz)) {
+                continue;
+            }
+            shown += 1;
+            println!("{} ({})", quiz.quiz_name, quiz.source);
+            quiz.print_metadata();
+        }
+        if let Some(filter) = filter {
+            if shown == 0 {
+                println!("No quizes have questions with {filter}.");
             }
         }
-        quiz
+        for problem in &self.problems {
+            println!("{} {problem}", "warning:".yellow());
+        }
     }
 }
 
 impl fmt::Display for QuizList {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for quiz in &self.0 {
+        for quiz in &self.quizes {
             write!(f, "{}", quiz.quiz_name)?
         }
         Ok(())
     }
 }
 
+/// share of the possible points, rounded to a whole percent between 0 and 100.
+fn percentage(points: f32, possible_points: f32) -> u8 {
+    if possible_points <= 0.0 {
+        return 0;
+    }
+    (points * 100.0 / possible_points).round().clamp(0.0, 100.0) as u8
+}
+
+/// An answer as a save holds it, saves from before scored answers only kept the question
+/// and the text of the picked answer.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedAnswer {
+    Scored(UserAnswer),
+    Legacy(Question, String),
+}
+
+/// reads the answers of a save, scoring the answers of old saves again.
+fn deserialize_user_answers<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Vec<UserAnswer>, D::Error> {
+    let saved_answers = Vec::<SavedAnswer>::deserialize(deserializer)?;
+    Ok(saved_answers
+        .into_iter()
+        .map(|saved_answer| match saved_answer {
+            SavedAnswer::Scored(user_answer) => user_answer,
+            SavedAnswer::Legacy(question, answer) => UserAnswer::from_legacy(question, answer),
+        })
+        .collect())
+}
+
+/// A score as a save holds it, old saves counted whole points.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedScore {
+    Points(f32),
+    Whole(u32),
+}
+
+/// reads the score of a save, whole numbers from old saves included.
+fn deserialize_score<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<f32, D::Error> {
+    Ok(match SavedScore::deserialize(deserializer)? {
+        SavedScore::Points(points) => points,
+        SavedScore::Whole(points) => points as f32,
+    })
+}
+
 /// Main Structure for single examination, holds collection of questions for user to answer.
 #[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
 pub struct Quiz {
     pub quiz_name: String,
     pub questions: Vec<Question>,
-    pub user_answers: Vec<(Question, String)>,
-    pub score: u32,
+    #[serde(deserialize_with = "deserialize_user_answers")]
+    pub user_answers: Vec<UserAnswer>,
+    /// points earned so far, partly right answers can earn part of a point
+    #[serde(deserialize_with = "deserialize_score")]
+    pub score: f32,
+    /// show right or wrong and the explanation straight after each question
+    #[serde(default)]
+    pub show_explanations: bool,
+    /// seconds for the whole quiz
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_limit: Option<u64>,
+    /// share of a question's points taken away for a wrong answer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub negative_marking: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grading: Option<GradingScale>,
+    #[serde(default, skip_serializing_if = "MessagePack::is_empty")]
+    pub messages: MessagePack,
+    /// seed the attempt was set up with, the questions are saved in the order it gave them.
+    /// none for saves from before seeds, their answers are shuffled at random
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// intros and time limits of the sections, their questions are in questions
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sections: Vec<Section>,
+    /// positions in questions of the asked questions in the order they were asked,
+    /// a resumed quiz carries on after the last one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<usize>,
+    /// seconds spent answering so far, kept in saves so the time limit carries over
+    #[serde(default)]
+    pub seconds_used: f32,
 }
 
 impl Quiz {
     /// if question name in answered questions, the score should reflect it
     fn check_answered_question(&self, current_question: &Question) -> bool {
         for answered_question in &self.user_answers {
-            if &answered_question.0 == current_question {
+            if &answered_question.question == current_question {
                 println!("this question has been answered - ignoring");
                 return true;
             } else {
@@ -103,6 +608,35 @@ impl Quiz {
         false
     }
 
+    /// true when any question has an explanation or an answer with a rationale
+    pub fn has_explanations(&self) -> bool {
+        self.questions.iter().any(|question| {
+            question.explanation.is_some()
+                || question
+                    .answers
+                    .iter()
+                    .any(|answer| answer.rationale().is_some())
+        })
+    }
+
+    /// asks the user whether to see explanations straight after answering each question
+    pub fn prompt_for_explanations(&mut self) {
+        println!("This quiz explains its answers. Would you like to see them right after each question? (yes/no)");
+        loop {
+            match tools::read_input().as_str() {
+                "yes" | "y" => {
+                    self.show_explanations = true;
+                    return;
+                }
+                "no" | "n" | "" => {
+                    self.show_explanations = false;
+                    return;
+                }
+                _ => println!("Please type 'yes' or 'no'."),
+            }
+        }
+    }
+
     /// Load a saved quiz progress into memory
     pub fn load(path: &Path) -> Result<Quiz> {
         let toml_str = fs::read_to_string(path)?;
@@ -134,35 +668,120 @@ impl Quiz {
     pub fn begin_quiz(mut self) -> Option<Quiz> {
         let mut save_and_quit_prompt = false;
         let mut loaded_saved_quiz = false;
-        let mut new_user_answers: Vec<(Question, String)> = Vec::new();
-        if self.user_answers.len() > 0 {
+        let mut new_user_answers: Vec<UserAnswer> = Vec::new();
+        if !self.user_answers.is_empty() {
             loaded_saved_quiz = true;
         }
 
-        // Cycle through questions
-        for question in &self.questions {
-            if loaded_saved_quiz {
-                if self.check_answered_question(&question) {
-                    continue; // skip question since it was answered
+        // time left is counted from now, time spent before a save was already used up
+        let quiz_deadline = self.time_limit.map(|time_limit| {
+            Instant::now()
+                + Duration::from_secs(time_limit)
+                    .saturating_sub(Duration::from_secs_f32(self.seconds_used))
+        });
+        let mut announced_time_up = false;
+        let mut current_section: Option<String> = None;
+        let mut section_deadline: Option<Instant> = None;
+        let mut announced_section_time_up = false;
+
+        // Walk through questions, each answer decides which question follows
+        let mut next = self.resume_position();
+        while let Some(position) = next {
+            let question = &self.questions[position];
+            if loaded_saved_quiz && self.check_answered_question(question) {
+                next = Some(position + 1).filter(|next| *next < self.questions.len());
+                continue; // skip question since it was answered
+            }
+
+            let quiz_time_up = quiz_deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            // entering a section shows its intro, then starts its own clock
+            if !quiz_time_up && question.section != current_section {
+                current_section = question.section.clone();
+                announced_section_time_up = false;
+                section_deadline = None;
+                if let Some(section) = self
+                    .sections
+                    .iter()
+                    .find(|section| Some(&section.name) == current_section.as_ref())
+                {
+                    let seconds_used = self.section_seconds_used(&section.name);
+                    if seconds_used == 0.0 {
+                        section.print_intro();
+                    }
+                    section_deadline = section.time_limit.map(|time_limit| {
+                        Instant::now()
+                            + Duration::from_secs(time_limit)
+                                .saturating_sub(Duration::from_secs_f32(seconds_used))
+                    });
                 }
             }
 
-            match question.ask() {
-                // TODO: IM NOT SURE YOU CAN MATCH TUPLES LIKE THIS
-                Some((user_answer, true)) => {
-                    self.score += 1;
-                    new_user_answers.push((question.clone(), user_answer));
+            let user_answer = if quiz_time_up {
+                if !announced_time_up {
+                    tools::clear_terminal();
+                    println!(
+                        "The time for this quiz is up, the remaining questions count as wrong."
+                    );
+                    println!("Press enter to see your result.");
+                    tools::read_input();
+                    announced_time_up = true;
                 }
-                Some((user_answer, false)) => {
-                    new_user_answers.push((question.clone(), user_answer));
-                    continue;
+                question.timed_out()
+            } else if section_deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                if !announced_section_time_up {
+                    tools::clear_terminal();
+                    println!(
+                        "The time for this section is up, its remaining questions count as wrong."
+                    );
+                    println!("Press enter to continue.");
+                    tools::read_input();
+                    announced_section_time_up = true;
                 }
-                None => {
-                    // only 'save and quit' input by user
-                    save_and_quit_prompt = true;
-                    break;
+                question.timed_out()
+            } else {
+                tools::clear_terminal();
+                if let Some(deadline) = quiz_deadline {
+                    println!(
+                        "Time left for this quiz: {}\n",
+                        tools::format_duration(deadline.saturating_duration_since(Instant::now()))
+                    );
                 }
-            }
+                if let Some(deadline) = section_deadline {
+                    println!(
+                        "Time left for this section: {}\n",
+                        tools::format_duration(deadline.saturating_duration_since(Instant::now()))
+                    );
+                }
+                let deadline = match (quiz_deadline, section_deadline) {
+                    (Some(quiz_deadline), Some(section_deadline)) => {
+                        Some(quiz_deadline.min(section_deadline))
+                    }
+                    (quiz_deadline, section_deadline) => quiz_deadline.or(section_deadline),
+                };
+                match question.ask_before(SAVE_AND_QUIT, deadline, self.layout_seed(position)) {
+                    Some(mut user_answer) => {
+                        self.penalize(&mut user_answer);
+                        self.seconds_used += user_answer.seconds_taken;
+                        if self.show_explanations {
+                            user_answer.print_verdict();
+                            user_answer.print_report();
+                            user_answer.print_explanation();
+                            println!("\nPress enter to continue.");
+                            tools::read_input();
+                        }
+                        self.score += user_answer.points;
+                        user_answer
+                    }
+                    None => {
+                        // only 'save and quit' input by user
+                        save_and_quit_prompt = true;
+                        break;
+                    }
+                }
+            };
+            next = branching::next_position(&self.questions, position, user_answer.is_correct());
+            self.path.push(position);
+            new_user_answers.push(user_answer);
         }
         // combine new user answers with user answers for reporting and saving
         for answer in new_user_answers {
@@ -185,281 +804,242 @@ impl Quiz {
         Some(self)
     }
 
-    /// creates a grade struct and prints the outcome of a quiz given a score and total quiz questions
-    /// it is largly standalone since score is not saved in the struct.
-    pub fn show_result(self) {
-        tools::clear_terminal();
-        let user_grade_percentage: u8 = (self.score * 100 / &self.get_quiz_length()) as u8;
-        let user_grade = Grade::from(user_grade_percentage);
-        println!(
-            "You got {} / {} correct. --- {}%",
-            self.score,
-            self.questions.len(),
-            user_grade_percentage
-        );
-        println!("{user_grade}");
-        user_grade.print_random_grade_message();
-
-        println!("type 'answers' if you would like to see what you got right and wrong. Otherwise just hit enter.");
-        loop {
-            let prompt = tools::read_input();
-            if prompt == "" {
-                break;
-            } else if prompt == "answers" {
-                self.display_user_answers();
-                break;
-            } else {
-                println!("Either type 'answer' to view answers or just press enter to return to main menu...");
+    /// position of the first question to ask, after the last asked one when resuming.
+    fn resume_position(&self) -> Option<usize> {
+        match (self.path.last(), self.user_answers.last()) {
+            (Some(&position), Some(user_answer)) => {
+                branching::next_position(&self.questions, position, user_answer.is_correct())
             }
+            // saves from before paths skip their answered questions one by one
+            _ => Some(0).filter(|_| !self.questions.is_empty()),
         }
     }
 
-    /// returns number of questions for a given quiz
-    pub fn get_quiz_length(&self) -> u32 {
-        self.questions.len() as u32
+    /// questions the score is out of, only those on the path taken when questions branch.
+    fn counted_questions(&self) -> Vec<&Question> {
+        if branching::is_branching(&self.questions) {
+            self.user_answers
+                .iter()
+                .map(|user_answer| &user_answer.question)
+                .collect()
+        } else {
+            self.questions.iter().collect()
+        }
     }
 
-    pub fn display_user_answers(&self) {
-        for report in &self.user_answers {
-            println!("Question: {}", report.0.question);
-            if report.0.answers[(report.0.correct_answer - 1) as usize] == report.1 {
-                println!("{} {}", "Your answer:".green(), report.1.green());
-            } else {
-                println!("{} {}", "Your answer:".red(), report.1.red());
-                println!(
-                    "{} {}",
-                    "Correct Answer:".green(),
-                    report.0.answers[(report.0.correct_answer - 1) as usize].green()
-                );
+    /// seconds spent on the section's answered questions, so a resumed section keeps its clock.
+    fn section_seconds_used(&self, section_name: &str) -> f32 {
+        self.user_answers
+            .iter()
+            .filter(|user_answer| user_answer.question.section.as_deref() == Some(section_name))
+            .map(|user_answer| user_answer.seconds_taken)
+            .sum()
+    }
+
+    /// seed for the answer order of the question at the position, none when the quiz has no seed.
+    pub fn layout_seed(&self, position: usize) -> Option<u64> {
+        // spread the positions out so neighbouring seeds do not share layouts
+        self.seed
+            .map(|seed| seed ^ (position as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+
+    /// takes the negative marking share off a wrong answer. skipped and timed out questions were never answered.
+    pub fn penalize(&self, user_answer: &mut UserAnswer) {
+        let Some(negative_marking) = self.negative_marking else {
+            return;
+        };
+        if user_answer.points == 0.0 && !user_answer.skipped && !user_answer.timed_out {
+            user_answer.points = -negative_marking * user_answer.question.max_points();
+        }
+    }
+
+    /// Study mode, shows feedback after every question and asks missed questions again at the end
+    /// until each one has been answered correctly. Nothing is saved or recorded.
+    pub fn study(self) {
+        let mut queue: VecDeque<&Question> = self.questions.iter().collect();
+        let mut attempts = 0;
+        let mut missed: Vec<&Question> = Vec::new();
+
+        while let Some(question) = queue.pop_front() {
+            tools::clear_terminal();
+            println!("{} - {} left to master\n", self.quiz_name, queue.len() + 1);
+            // every attempt gets its own layout, so a question that comes back is shuffled again
+            let user_answer =
+                match question.ask_before(STOP_STUDYING, None, self.layout_seed(attempts)) {
+                    Some(user_answer) => user_answer,
+                    None => return,
+                };
+            attempts += 1;
+
+            user_answer.print_verdict();
+            user_answer.print_report();
+            user_answer.print_explanation();
+            if !user_answer.is_correct() {
+                if !missed.contains(&question) {
+                    missed.push(question);
+                }
+                queue.push_back(question);
+                println!("\nThis question will come back later.");
             }
-            println!();
+            println!("\nPress enter to continue.");
+            tools::read_input();
         }
-        let user_grade_percentage: u8 = (self.score * 100 / &self.get_quiz_length()) as u8;
 
+        tools::clear_terminal();
         println!(
-            "You got {} / {} correct. --- {}%",
-            self.score,
+            "You mastered all {} questions of {} in {} attempts.",
             self.questions.len(),
-            user_grade_percentage
+            self.quiz_name,
+            attempts
         );
+        if !missed.is_empty() {
+            println!("Questions that took more than one try:");
+            for question in missed {
+                println!("  {}", question.question);
+            }
+        }
         println!("Press enter to return to main menu.");
         tools::read_input();
     }
-}
-
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
-pub struct Question {
-    pub question: String,
-    pub answers: Vec<String>,
-    pub correct_answer: i8,
-}
 
-impl Question {
-    fn ask(&self) -> Option<(String, bool)> {
+    /// grades and prints the outcome of a quiz, the quiz's own scale and messages win over the config.
+    pub fn show_result(self, config: &Config) {
         tools::clear_terminal();
-        let mut rng = thread_rng();
-
-        println!("{}", self.question);
-        // original answer vector will act as answer key
-        let mut shuffled_answers = self.answers.clone();
-        shuffled_answers.shuffle(&mut rng);
-
-        for suffled_answer in shuffled_answers.iter().enumerate() {
-            println!("[{}] {}", suffled_answer.0 + 1, suffled_answer.1);
+        let user_grade_percentage = self.get_percentage();
+        self.print_score();
+        self.print_time();
+        let scale = self.grading.as_ref().unwrap_or(&config.grading);
+        let messages = self.messages.clone().or(&config.messages);
+        if let Some(user_grade) = scale.grade(user_grade_percentage, &messages) {
+            println!("{user_grade}");
+            user_grade.print_random_grade_message();
+        }
+        self.print_section_results(scale, &messages);
+        if let Err(e) = History::record(
+            &self.quiz_name,
+            self.score,
+            self.get_possible_points(),
+            user_grade_percentage,
+        ) {
+            println!("Could not record result in history: {e}");
         }
-        println!("Enter the number next to the answer you beleive is correct.");
-        println!("Type 'save and quit' if you would like to do so.");
-        loop {
-            let user_input = tools::read_input();
 
-            if user_input == "save and quit" {
-                return None; // begin generating save file.
-            }
-            let user_answer: usize = match user_input.parse() {
-                Ok(num) => num,
-                Err(_) => {
-                    println!(
-                        "Invaild input, please enter your guess by typing its corresponding number"
-                    );
-                    continue;
-                }
-            };
-            if !(1..5).contains(&user_answer) {
-                println!("Invaild input, please enter one of the available answers.");
-                continue;
-            }
-            println!();
-            if shuffled_answers[user_answer - 1] == self.answers[self.correct_answer as usize - 1] {
-                return Some((shuffled_answers[user_answer - 1].clone(), true));
+        println!("type 'answers' if you would like to see what you got right and wrong. Otherwise just hit enter.");
+        loop {
+            let prompt = tools::read_input();
+            if prompt.is_empty() {
+                break;
+            } else if prompt == "answers" {
+                self.display_user_answers();
+                break;
             } else {
-                return Some((shuffled_answers[user_answer - 1].clone(), false));
+                println!("Either type 'answer' to view answers or just press enter to return to main menu...");
             }
         }
     }
-}
 
-#[derive(PartialEq, Debug)]
-
-enum LetterGrade {
-    A,
-    B,
-    C,
-    D,
-    F,
-}
-impl fmt::Display for LetterGrade {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            LetterGrade::A => write!(f, "A"),
-            LetterGrade::B => write!(f, "B"),
-            LetterGrade::C => write!(f, "C"),
-            LetterGrade::D => write!(f, "D"),
-            LetterGrade::F => write!(f, "F"),
+    /// prints points, percentage and grade of every section, so weak areas stand out.
+    fn print_section_results(&self, scale: &GradingScale, messages: &MessagePack) {
+        if self.sections.is_empty() {
+            return;
+        }
+        println!("\nSections:");
+        for section in &self.sections {
+            let in_section = |question: &Question| question.section.as_ref() == Some(&section.name);
+            let possible_points: f32 = self
+                .counted_questions()
+                .into_iter()
+                .filter(|question| in_section(question))
+                .map(Question::max_points)
+                .sum();
+            let points: f32 = self
+                .user_answers
+                .iter()
+                .filter(|user_answer| in_section(&user_answer.question))
+                .map(|user_answer| user_answer.points)
+                .sum();
+            // a branching quiz can go past a section without asking any of it
+            if possible_points == 0.0 {
+                continue;
+            }
+            let percentage = percentage(points, possible_points);
+            let grade = match scale.grade(percentage, messages) {
+                Some(grade) => format!(", {grade}"),
+                None => String::new(),
+            };
+            println!(
+                "  {}: {} / {} points. --- {percentage}%{grade}",
+                section.name,
+                tools::format_points(points),
+                tools::format_points(possible_points),
+            );
         }
+        println!();
     }
-}
-
-#[derive(PartialEq, Debug)]
-enum LetterGradeModifier {
-    Plus,
-    Mid,
-    Minus,
-}
 
-struct Grade {
-    grade: LetterGrade,
-    modifer: LetterGradeModifier,
-}
-
-impl Grade {
-    const A_MESSAGES: &'static [&'static str; 5] = &[
-        "Fantastic work!",
-        "Oh shit!",
-        "Jesus!",
-        "Fucking Hell!",
-        "I'm not worthy of your presence!",
-    ];
-    const B_MESSAGES: &'static [&'static str; 5] = &[
-        "Nice.",
-        "Could've been better.",
-        "Alright, good job!",
-        "Close enough I suppose.",
-        "Nice work keeping above C level.",
-    ];
-    const C_MESSAGES: &'static [&'static str] = &[
-        "Acceptable.",
-        "Ok. Sure.",
-        "Nothing special.",
-        "Cs get degrees.",
-    ];
-    const D_MESSAGES: &'static [&'static str; 5] = &[
-        "Cutting it close eh?",
-        "Bah, you'll get em next time.",
-        "Hey, that's passing right?",
-        "Do better.",
-        "You got this. Never surrender. Give it another try.",
-    ];
-    const F_MESSAGES: &'static [&'static str; 7] = &[
-        "Damn, you fucking suck.",
-        "Jesus man. Really?",
-        "Were you even trying?",
-        "Looks like all those brain cells really are gone.",
-        "Sheeesh, nice work bro.",
-        "Fucking dumb ass.",
-        "Bruh",
-    ];
-}
+    /// returns the points earned by answering every question correctly
+    pub fn get_possible_points(&self) -> f32 {
+        self.counted_questions()
+            .into_iter()
+            .map(Question::max_points)
+            .sum()
+    }
 
-impl Grade {
-    /// prints a random message from the stored grade messages.
-    pub fn print_random_grade_message(self) {
-        let mut rng = thread_rng();
-        let messages = match self.grade {
-            LetterGrade::A => Grade::A_MESSAGES,
-            LetterGrade::B => Grade::B_MESSAGES,
-            LetterGrade::C => Grade::C_MESSAGES,
-            LetterGrade::D => Grade::D_MESSAGES,
-            LetterGrade::F => Grade::F_MESSAGES,
-        };
+    /// returns the share of possible points earned, rounded to a whole percent
+    pub fn get_percentage(&self) -> u8 {
+        percentage(self.score, self.get_possible_points())
+    }
 
-        for message in messages.choose_multiple(&mut rng, 1) {
-            println!("{message}");
-        }
+    fn print_score(&self) {
+        println!(
+            "You got {} / {} points. --- {}%",
+            tools::format_points(self.score),
+            tools::format_points(self.get_possible_points()),
+            self.get_percentage()
+        );
     }
-}
 
-impl fmt::Display for Grade {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.modifer == LetterGradeModifier::Plus {
-            write!(f, "Grade: {}+", self.grade)
-        } else if self.modifer == LetterGradeModifier::Mid {
-            write!(f, "Grade: {}", self.grade)
-        } else {
-            write!(f, "Grade: {}-", self.grade)
+    fn print_time(&self) {
+        let answered = self
+            .user_answers
+            .iter()
+            .filter(|user_answer| user_answer.seconds_taken > 0.0)
+            .count();
+        if answered == 0 {
+            return;
+        }
+        let time_used = Duration::from_secs(self.seconds_used.round() as u64);
+        print!(
+            "Time taken: {}, {:.1}s per question on average",
+            tools::format_duration(time_used),
+            self.seconds_used / answered as f32
+        );
+        match self.time_limit {
+            Some(time_limit) => println!(
+                " (limit {}).",
+                tools::format_duration(Duration::from_secs(time_limit))
+            ),
+            None => println!("."),
         }
     }
-}
 
-impl From<u8> for Grade {
-    fn from(score: u8) -> Self {
-        match score {
-            97..=255 => Grade {
-                // Should never be over 100
-                grade: LetterGrade::A,
-                modifer: LetterGradeModifier::Plus,
-            },
-            94..=96 => Grade {
-                grade: LetterGrade::A,
-                modifer: LetterGradeModifier::Mid,
-            },
-            90..=93 => Grade {
-                grade: LetterGrade::A,
-                modifer: LetterGradeModifier::Minus,
-            },
-            87..=89 => Grade {
-                grade: LetterGrade::B,
-                modifer: LetterGradeModifier::Plus,
-            },
-            84..=86 => Grade {
-                grade: LetterGrade::B,
-                modifer: LetterGradeModifier::Mid,
-            },
-            80..=83 => Grade {
-                grade: LetterGrade::B,
-                modifer: LetterGradeModifier::Minus,
-            },
-            77..=79 => Grade {
-                grade: LetterGrade::C,
-                modifer: LetterGradeModifier::Plus,
-            },
-            74..=76 => Grade {
-                grade: LetterGrade::C,
-                modifer: LetterGradeModifier::Mid,
-            },
-            70..=73 => Grade {
-                grade: LetterGrade::C,
-                modifer: LetterGradeModifier::Minus,
-            },
-            67..=69 => Grade {
-                grade: LetterGrade::D,
-                modifer: LetterGradeModifier::Plus,
-            },
-            64..=66 => Grade {
-                grade: LetterGrade::D,
-                modifer: LetterGradeModifier::Mid,
-            },
-            60..=63 => Grade {
-                grade: LetterGrade::D,
-                modifer: LetterGradeModifier::Minus,
-            },
-            50..=59 => Grade {
-                grade: LetterGrade::F,
-                modifer: LetterGradeModifier::Plus,
-            },
-            0..=49 => Grade {
-                grade: LetterGrade::F,
-                modifer: LetterGradeModifier::Minus,
-            },
+    pub fn display_user_answers(&self) {
+        for report in &self.user_answers {
+            let label = "Question:";
+            println!(
+                "{label} {}",
+                markdown::render(&report.question.prompt_text(), label.len() + 1)
+            );
+            report.print_report();
+            report.print_explanation();
+            if report.seconds_taken > 0.0 {
+                println!("Time: {:.1}s", report.seconds_taken);
+            }
+            println!();
         }
+
+        self.print_score();
+        println!("Press enter to return to main menu.");
+        tools::read_input();
     }
 }
@@ -0,0 +1,377 @@
+use anyhow::{bail, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::expression::Expression;
+use crate::question::{AnswerOption, Question, QuestionKind};
+
+/// name the distractor rules and texts use for the computed answer.
+const ANSWER: &str = "answer";
+/// draws tried before giving up on values that keep giving no answer, like a division by zero
+const MAX_DRAWS: usize = 100;
+/// draws tried when checking a template on loading
+const CHECK_DRAWS: u64 = 50;
+
+/// Turns a question into a drill, generating new numbers for every attempt.
+/// {name} in the question text and explanation is replaced with the drawn value of a variable,
+/// {answer} with the computed answer.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Template {
+    /// inclusive lowest and highest whole number of every variable
+    pub variables: BTreeMap<String, [i64; 2]>,
+    /// expression over the variables giving the correct answer
+    pub answer: String,
+    /// expressions giving the wrong answers of multiple choice questions, they can use answer
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub distractors: Vec<String>,
+    /// decimals numbers are rounded to
+    #[serde(
+        default = "default_decimals",
+        skip_serializing_if = "is_default_decimals"
+    )]
+    pub decimals: u32,
+}
+
+fn default_decimals() -> u32 {
+    2
+}
+
+fn is_default_decimals(decimals: &u32) -> bool {
+    *decimals == default_decimals()
+}
+
+impl Template {
+    /// Lists everything wrong with the template of the question.
+    pub fn problems(&self, question: &Question) -> Vec<String> {
+        let mut problems: Vec<String> = Vec::new();
+        if !matches!(
+            question.kind,
+            QuestionKind::MultipleChoice | QuestionKind::Numeric | QuestionKind::FreeText
+        ) {
+            problems.push(
+                "templates only work with multiple_choice, numeric or free_text questions"
+                    .to_string(),
+            );
+            return problems;
+        }
+        if !question.answers.is_empty()
+            || question.correct_answer.is_some()
+            || !question.accepted_answers.is_empty()
+            || question.numeric_answer.is_some()
+            || question.range.is_some()
+        {
+            problems
+                .push("the template computes the answer, leave the answer fields out".to_string());
+        }
+        if question.kind == QuestionKind::MultipleChoice && self.distractors.is_empty() {
+            problems.push("multiple choice templates need at least one distractor".to_string());
+        }
+        if question.kind != QuestionKind::MultipleChoice && !self.distractors.is_empty() {
+            problems.push("only multiple choice templates use distractors".to_string());
+        }
+        for (name, [lowest, highest]) in &self.variables {
+            let is_name = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+            if !is_name || name == ANSWER {
+                problems.push(format!("'{name}' can not be used as a variable name"));
+            }
+            if lowest > highest {
+                problems.push(format!(
+                    "variable {name} goes from {lowest} to {highest}, the lowest must come first"
+                ));
+            }
+        }
+
+        let mut expressions = vec![(&self.answer, false)];
+        expressions.extend(self.distractors.iter().map(|distractor| (distractor, true)));
+        for (text, uses_answer) in expressions {
+            match Expression::parse(text) {
+                Ok(expression) => {
+                    for variable in expression.variables() {
+                        let known = self.variables.contains_key(variable)
+                            || (uses_answer && variable == ANSWER);
+                        if !known {
+                            problems.push(format!(
+                                "'{text}' uses '{variable}' which is not a variable"
+                            ));
+                        }
+                    }
+                }
+                Err(e) => problems.push(e.to_string()),
+            }
+        }
+        for text in [Some(&question.question), question.explanation.as_ref()]
+            .into_iter()
+            .flatten()
+        {
+            for name in placeholders(text) {
+                if !self.variables.contains_key(name) && name != ANSWER {
+                    problems.push(format!("{{{name}}} is not a variable"));
+                }
+            }
+        }
+        if !problems.is_empty() {
+            return problems;
+        }
+
+        // try a few seeded draws so values that give no answer are found before playing
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..CHECK_DRAWS {
+            match self.instantiate(question, &mut rng) {
+                Ok(instance) => {
+                    problems.extend(instance.problems());
+                    if !problems.is_empty() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    problems.push(e.to_string());
+                    break;
+                }
+            }
+        }
+        problems
+    }
+
+    /// A concrete question with freshly drawn values, redrawing values that give no answer.
+    pub fn instantiate(&self, question: &Question, rng: &mut impl Rng) -> Result<Question> {
+        let mut last_error = None;
+        for _ in 0..MAX_DRAWS {
+            let values: BTreeMap<String, f64> = self
+                .variables
+                .iter()
+                .map(|(name, [lowest, highest])| {
+                    (name.clone(), rng.gen_range(*lowest..=*highest) as f64)
+                })
+                .collect();
+            match self.instance_with(question, values) {
+                Ok(instance) => return Ok(instance),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.expect("every draw failed with an error"))
+    }
+
+    /// fills in the question with the drawn values.
+    fn instance_with(
+        &self,
+        question: &Question,
+        mut values: BTreeMap<String, f64>,
+    ) -> Result<Question> {
+        let answer = self.rounded(Expression::parse(&self.answer)?.evaluate(&values)?, &values)?;
+        values.insert(ANSWER.to_string(), answer);
+
+        let mut instance = question.clone();
+        instance.template = None;
+        instance.question = fill(&question.question, &values, self.decimals);
+        instance.explanation = question
+            .explanation
+            .as_ref()
+            .map(|explanation| fill(explanation, &values, self.decimals));
+        match question.kind {
+            QuestionKind::Numeric => instance.numeric_answer = Some(answer),
+            QuestionKind::FreeText => {
+                instance.accepted_answers = vec![format_number(answer, self.decimals)]
+            }
+            _ => {
+                instance.answers = self
+                    .options(answer, &values)?
+                    .into_iter()
+                    .map(AnswerOption::Text)
+                    .collect();
+                instance.correct_answer = Some(1);
+            }
+        }
+        Ok(instance)
+    }
+
+    /// the answer followed by one distinct wrong answer per distractor rule.
+    /// rules that land on a taken number are replaced by the nearest free whole step from the answer
+    fn options(&self, answer: f64, values: &BTreeMap<String, f64>) -> Result<Vec<String>> {
+        let mut options = vec![format_number(answer, self.decimals)];
+        for distractor in &self.distractors {
+            let value = self.rounded(Expression::parse(distractor)?.evaluate(values)?, values)?;
+            let mut option = format_number(value, self.decimals);
+            let mut step = 1.0;
+            while options.contains(&option) {
+                option = format_number(answer + step, self.decimals);
+                step = if step > 0.0 { -step } else { 1.0 - step };
+            }
+            options.push(option);
+        }
+        Ok(options)
+    }
+
+    /// rounds to the template decimals, failing for values that are no number.
+    fn rounded(&self, value: f64, values: &BTreeMap<String, f64>) -> Result<f64> {
+        if !value.is_finite() {
+            let drawn: Vec<String> = values
+                .iter()
+                .map(|(name, value)| format!("{name} = {value}"))
+                .collect();
+            bail!(
+                "the template gives no number for {}, like when dividing by zero",
+                drawn.join(", ")
+            );
+        }
+        let scale = 10f64.powi(self.decimals as i32);
+        // adding zero turns -0 into 0
+        Ok((value * scale).round() / scale + 0.0)
+    }
+}
+
+/// A concrete copy of the question, generated when it has a template.
+pub fn instantiate(question: &Question, rng: &mut impl Rng) -> Result<Question> {
+    match &question.template {
+        Some(template) => template.instantiate(question, rng),
+        None => Ok(question.clone()),
+    }
+}
+
+/// names inside single braces, like a in "what is {a} * 2?".
+fn placeholders(text: &str) -> Vec<&str> {
+    let mut names: Vec<&str> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        match rest.find('}') {
+            Some(end) => {
+                names.push(&rest[..end]);
+                rest = &rest[end + 1..];
+            }
+            None => break,
+        }
+    }
+    names
+}
+
+/// replaces every {name} with its value.
+fn fill(text: &str, values: &BTreeMap<String, f64>, decimals: u32) -> String {
+    let mut filled = text.to_string();
+    for (name, value) in values {
+        filled = filled.replace(&format!("{{{name}}}"), &format_number(*value, decimals));
+    }
+    filled
+}
+
+/// the number with at most the given decimals, whole numbers without any.
+fn format_number(value: f64, decimals: u32) -> String {
+    let scale = 10f64.powi(decimals as i32);
+    format!("{}", (value * scale).round() / scale + 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn question(toml: &str) -> Question {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn values(values: &[(&str, f64)]) -> BTreeMap<String, f64> {
+        values
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect()
+    }
+
+    fn multiply() -> Question {
+        question(
+            r#"
+            question = "What is {a} * {b}?"
+            explanation = "{a} * {b} = {answer}"
+            [template]
+                variables = { a = [2, 12], b = [2, 12] }
+                answer = "a * b"
+                distractors = ["answer", "answer + 1", "a + b"]
+            "#,
+        )
+    }
+
+    #[test]
+    fn distractors_taken_by_another_option_move_to_the_nearest_free_step() {
+        let template = multiply().template.unwrap();
+        let options = template
+            .options(12.0, &values(&[("a", 3.0), ("b", 4.0), ("answer", 12.0)]))
+            .unwrap();
+        // "answer" lands on 12 and moves to 13, "answer + 1" then lands on 13 and moves to 11
+        assert_eq!(options, vec!["12", "13", "11", "7"]);
+    }
+
+    #[test]
+    fn fills_text_and_puts_the_answer_first() {
+        let question = multiply();
+        let instance = question
+            .template
+            .as_ref()
+            .unwrap()
+            .instance_with(&question, values(&[("a", 3.0), ("b", 4.0)]))
+            .unwrap();
+        assert_eq!(instance.question, "What is 3 * 4?");
+        assert_eq!(instance.explanation.as_deref(), Some("3 * 4 = 12"));
+        assert_eq!(instance.correct_answer, Some(1));
+        assert_eq!(instance.answers[0].text(), "12");
+        assert!(instance.template.is_none());
+    }
+
+    #[test]
+    fn numbers_are_rounded_to_the_decimals() {
+        assert_eq!(format_number(1.0 / 3.0, 2), "0.33");
+        assert_eq!(format_number(2.5, 0), "3");
+        assert_eq!(format_number(-0.001, 2), "0");
+        assert_eq!(format_number(1000.0, 2), "1000");
+    }
+
+    #[test]
+    fn same_seed_draws_the_same_question() {
+        let question = multiply();
+        let draw = |seed| {
+            instantiate(&question, &mut StdRng::seed_from_u64(seed))
+                .unwrap()
+                .question
+        };
+        assert_eq!(draw(7), draw(7));
+        assert!((0..20).any(|seed| draw(seed) != draw(7)));
+    }
+
+    #[test]
+    fn reports_template_mistakes() {
+        let question = question(
+            r#"
+            question = "What is {a} / {c}?"
+            [template]
+                variables = { a = [5, 1] }
+                answer = "a / b"
+            "#,
+        );
+        let problems = question.problems();
+        assert_eq!(
+            problems,
+            vec![
+                "multiple choice templates need at least one distractor",
+                "variable a goes from 5 to 1, the lowest must come first",
+                "'a / b' uses 'b' which is not a variable",
+                "{c} is not a variable",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_values_that_never_give_an_answer() {
+        let question = question(
+            r#"
+            question = "1 / {a}?"
+            kind = "numeric"
+            [template]
+                variables = { a = [0, 0] }
+                answer = "1 / a"
+            "#,
+        );
+        assert_eq!(
+            question.problems(),
+            vec!["the template gives no number for a = 0, like when dividing by zero"]
+        );
+    }
+}
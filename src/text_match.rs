@@ -0,0 +1,138 @@
+use unicode_normalization::UnicodeNormalization;
+
+/// answers get one forgiven typo per this many characters, so short answers need to be exact
+const CHARS_PER_TYPO: usize = 4;
+
+/// Brings typed text into a comparable form.
+/// Unicode compatibility normalized, lowercased, trimmed and with inner whitespace collapsed to single spaces.
+pub fn normalize(text: &str) -> String {
+    text.nfkc()
+        .collect::<String>()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Number of single character insertions, deletions, substitutions or swaps of neighbouring characters
+/// to turn one text into the other.
+pub fn edit_distance(from: &str, to: &str) -> usize {
+    let from: Vec<char> = from.chars().collect();
+    let to: Vec<char> = to.chars().collect();
+    // distances[i][j] is the distance between the first i chars of from and the first j chars of to
+    let mut distances = vec![vec![0; to.len() + 1]; from.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=from.len() {
+        for j in 1..=to.len() {
+            let substitution_cost = usize::from(from[i - 1] != to[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + substitution_cost);
+            if i > 1 && j > 1 && from[i - 1] == to[j - 2] && from[i - 2] == to[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[from.len()][to.len()]
+}
+
+/// Finds the accepted answer the input matches, allowing up to max_typos edits after normalizing.
+/// Shorter answers allow fewer typos, see CHARS_PER_TYPO. An exact match wins over a match with typos.
+pub fn find_match<'a>(input: &str, accepted: &'a [String], max_typos: usize) -> Option<&'a str> {
+    let input = normalize(input);
+    let mut closest: Option<(&str, usize)> = None;
+    for answer in accepted {
+        let answer_text = normalize(answer);
+        let allowed_typos = max_typos.min(answer_text.chars().count() / CHARS_PER_TYPO);
+        let distance = edit_distance(&input, &answer_text);
+        if distance <= allowed_typos && closest.is_none_or(|(_, best)| distance < best) {
+            closest = Some((answer, distance));
+        }
+    }
+    closest.map(|(answer, _)| answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accepted(answers: &[&str]) -> Vec<String> {
+        answers.iter().map(|answer| answer.to_string()).collect()
+    }
+
+    #[test]
+    fn normalizes_case_whitespace_and_unicode_forms() {
+        assert_eq!(
+            normalize("  The   Borrow\tChecker \n"),
+            "the borrow checker"
+        );
+        // compatibility forms like full width letters and ligatures become plain letters
+        assert_eq!(normalize("Ｒｕｓｔ"), "rust");
+        assert_eq!(normalize("ﬁle"), "file");
+        // a composed é and e with a combining accent are the same
+        assert_eq!(normalize("Caf\u{e9}"), normalize("Cafe\u{301}"));
+        assert_eq!(normalize(" \t "), "");
+    }
+
+    #[test]
+    fn counts_single_character_edits() {
+        assert_eq!(edit_distance("ownership", "ownership"), 0);
+        assert_eq!(edit_distance("ownership", "ownrship"), 1);
+        assert_eq!(edit_distance("ownership", "owner-ship"), 1);
+        assert_eq!(edit_distance("ownership", "ownarship"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn swapped_neighbours_are_a_single_edit() {
+        assert_eq!(edit_distance("lifetime", "lifeitme"), 1);
+        assert_eq!(edit_distance("ab", "ba"), 1);
+        // only neighbours count as one swap
+        assert_eq!(edit_distance("abc", "cba"), 2);
+    }
+
+    #[test]
+    fn matches_within_the_allowed_typos() {
+        let answers = accepted(&["Borrow Checker", "borrowck"]);
+        assert_eq!(
+            find_match("  borrow   CHECKER", &answers, 0),
+            Some("Borrow Checker")
+        );
+        assert_eq!(find_match("borow checker", &answers, 0), None);
+        assert_eq!(
+            find_match("borow checker", &answers, 1),
+            Some("Borrow Checker")
+        );
+        assert_eq!(find_match("brorow chekcer", &answers, 1), None);
+        assert_eq!(
+            find_match("brorow chekcer", &answers, 2),
+            Some("Borrow Checker")
+        );
+    }
+
+    #[test]
+    fn short_answers_allow_fewer_typos() {
+        let answers = accepted(&["a", "mut", "move", "borrowck"]);
+        assert_eq!(find_match("b", &answers, 2), None);
+        assert_eq!(find_match("a", &answers, 2), Some("a"));
+        assert_eq!(find_match("mat", &answers, 2), None);
+        // one typo from four characters on, two from eight
+        assert_eq!(find_match("mvoe", &answers, 2), Some("move"));
+        assert_eq!(find_match("mvo", &answers, 2), None);
+        assert_eq!(find_match("borowk", &answers, 2), Some("borrowck"));
+        assert_eq!(find_match("borowk", &answers, 1), None);
+    }
+
+    #[test]
+    fn an_exact_match_wins_over_a_typo() {
+        let answers = accepted(&["rustc", "rust"]);
+        assert_eq!(find_match("rust", &answers, 1), Some("rust"));
+        assert_eq!(find_match("rustcc", &answers, 1), Some("rustc"));
+    }
+}
@@ -1,16 +1,122 @@
-use std::io;
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use std::{env, path::PathBuf};
+
+/// Lines typed by the user. Stdin is read on its own thread so waiting for input can time out.
+static INPUT_LINES: OnceLock<Mutex<Receiver<String>>> = OnceLock::new();
+
+fn input_lines() -> &'static Mutex<Receiver<String>> {
+    INPUT_LINES.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || loop {
+            let mut input = String::new();
+            match io::stdin().read_line(&mut input) {
+                // end of input closes the channel
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if sender.send(input).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        Mutex::new(receiver)
+    })
+}
 
 /// Generic function to ask user for input, trimed and lowercased.
 pub fn read_input() -> String {
-    let mut input = String::new();
-    io::stdin()
-        .read_line(&mut input)
-        .expect("Failed to read line");
+    read_raw_input().to_lowercase()
+}
 
-    String::from(input.trim()).to_lowercase()
+/// Asks user for input, trimed but keeps its casing. Used for names and other displayed text.
+pub fn read_raw_input() -> String {
+    let input = input_lines()
+        .lock()
+        .expect("input lock poisoned")
+        .recv()
+        .unwrap_or_default(); // closed stdin reads as empty lines, like read_line does
+
+    String::from(input.trim())
+}
+
+/// Like read_raw_input but gives up at the deadline, returning none.
+/// A countdown line is printed above the input line and redrawn every second.
+pub fn read_raw_input_until(deadline: Instant) -> Option<String> {
+    let lines = input_lines().lock().expect("input lock poisoned");
+    println!(
+        "Time left: {}",
+        format_duration(deadline.saturating_duration_since(Instant::now()))
+    );
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            redraw_countdown("Time is up!");
+            return None;
+        }
+        // wake up when the shown whole second changes
+        let wait = match remaining.subsec_nanos() {
+            0 => Duration::from_secs(1),
+            nanos => Duration::from_nanos(nanos as u64),
+        };
+        match lines.recv_timeout(wait.min(remaining)) {
+            // a line sent just as time ran out still counts as late
+            Ok(_) if Instant::now() > deadline => {
+                redraw_countdown("Time is up!");
+                return None;
+            }
+            Ok(input) => return Some(String::from(input.trim())),
+            Err(RecvTimeoutError::Timeout) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if !remaining.is_zero() {
+                    redraw_countdown(&format!("Time left: {}", format_duration(remaining)));
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => return Some(String::new()),
+        }
+    }
+}
+
+/// rewrites the line above the cursor, leaving whatever the user is typing in place.
+fn redraw_countdown(text: &str) {
+    print!("\x1B7\x1B[1A\r\x1B[2K{text}\x1B8");
+    io::stdout().flush().ok();
+}
+
+/// Formats a duration as whole seconds, with minutes once it is a minute or longer.
+pub fn format_duration(duration: Duration) -> String {
+    // round up so the countdown shows 1s until time is actually up
+    let seconds = duration.as_secs() + u64::from(duration.subsec_nanos() > 0);
+    if seconds >= 60 {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    } else {
+        format!("{seconds}s")
+    }
 }
 
 /// Generic function to position terminal to only show most recent information.
 pub fn clear_terminal() {
     print!("\x1B[2J\x1B[1;1H");
 }
+
+/// Directory for files the game writes on behalf of the user, like imported quizes and history.
+/// Follows XDG, falls back to ~/.local/share and finally the working directory.
+pub fn data_dir() -> PathBuf {
+    let base = match env::var_os("XDG_DATA_HOME") {
+        Some(xdg_data_home) if !xdg_data_home.is_empty() => PathBuf::from(xdg_data_home),
+        _ => match env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(".local").join("share"),
+            None => PathBuf::from("."),
+        },
+    };
+    base.join("rust_quiz_game")
+}
+
+/// Formats points with at most two decimals, whole points are shown without any.
+pub fn format_points(points: f32) -> String {
+    let rounded = (points * 100.0).round() / 100.0;
+    format!("{rounded}")
+}